@@ -0,0 +1,68 @@
+//! Compares picking a category with `ORDER BY random()` against the keyed lookup used by
+//! `DB::random_jeopardy_category`, and against also giving the picked category a new key, which
+//! is what evening out the keys on every pick would cost. Needs `DATABASE_URL` pointing at a
+//! seeded database, which the rekeying leaves as it was.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use db::filter::CategoryFilter;
+use db::models::{JeopardyCategory, JeopardyQuestion};
+use db::schema::jeopardy_categories as jc;
+use diesel::prelude::*;
+
+fn random_category(c: &mut Criterion) {
+    dotenv::dotenv().ok();
+    let url = db::DB::env_url().expect("DATABASE_URL must be set");
+    let mut db = db::DB::new(&url).unwrap();
+    let mut conn = PgConnection::establish(&url).unwrap();
+
+    let mut group = c.benchmark_group("random_category");
+    group.bench_function("order_by_random", |b| {
+        b.iter(|| {
+            let category = jc::table
+                .filter(jc::question_count.eq(5))
+                .order(diesel::dsl::sql::<diesel::sql_types::Double>("random()"))
+                .first::<JeopardyCategory>(&mut conn)
+                .unwrap();
+            JeopardyQuestion::belonging_to(&category)
+                .load::<JeopardyQuestion>(&mut conn)
+                .unwrap()
+        })
+    });
+    group.bench_function("keyed", |b| {
+        b.iter(|| db.random_jeopardy_category().unwrap())
+    });
+    conn.begin_test_transaction().unwrap();
+    group.bench_function("keyed_rekeyed", |b| {
+        b.iter(|| {
+            let category = jc::table
+                .filter(jc::question_count.eq(5))
+                .filter(
+                    jc::random_key.ge(diesel::dsl::sql::<diesel::sql_types::Double>(
+                        "(SELECT random())",
+                    )),
+                )
+                .order(jc::random_key)
+                .first::<JeopardyCategory>(&mut conn)
+                .unwrap();
+            diesel::update(jc::table.find(category.id))
+                .set(jc::random_key.eq(diesel::dsl::sql::<diesel::sql_types::Double>("random()")))
+                .execute(&mut conn)
+                .unwrap();
+            JeopardyQuestion::belonging_to(&category)
+                .load::<JeopardyQuestion>(&mut conn)
+                .unwrap()
+        })
+    });
+    let filter = CategoryFilter::new()
+        .complete()
+        .round(jeopardy::Round::DoubleJeopardy)
+        .from_year(1995)
+        .to_year(2005);
+    group.bench_function("keyed_filtered", |b| {
+        b.iter(|| db.filtered_jeopardy_category(&filter).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, random_category);
+criterion_main!(benches);
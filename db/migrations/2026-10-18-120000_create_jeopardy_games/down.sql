@@ -0,0 +1,2 @@
+DROP TABLE jeopardy_game_answers;
+DROP TABLE jeopardy_games;
@@ -0,0 +1,23 @@
+CREATE TABLE jeopardy_games (
+    id UUID PRIMARY KEY,
+    discord_channel_id BIGINT NOT NULL,
+    discord_guild_id BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT diesel_manage_updated_at('jeopardy_games');
+
+CREATE TABLE jeopardy_game_answers (
+    id UUID PRIMARY KEY,
+    jeopardy_game_id UUID NOT NULL REFERENCES jeopardy_games(id),
+    jeopardy_question_id UUID NOT NULL REFERENCES jeopardy_questions(id),
+    discord_user_id BIGINT NOT NULL,
+    response TEXT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    value INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT diesel_manage_updated_at('jeopardy_game_answers');
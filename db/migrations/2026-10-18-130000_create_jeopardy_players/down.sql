@@ -0,0 +1 @@
+DROP TABLE jeopardy_players;
@@ -0,0 +1,17 @@
+CREATE TABLE jeopardy_players (
+    discord_guild_id BIGINT NOT NULL,
+    discord_user_id BIGINT NOT NULL,
+    games_played INT NOT NULL DEFAULT 0,
+    correct INT NOT NULL DEFAULT 0,
+    incorrect INT NOT NULL DEFAULT 0,
+    winnings BIGINT NOT NULL DEFAULT 0,
+    current_streak INT NOT NULL DEFAULT 0,
+    best_streak INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (discord_guild_id, discord_user_id)
+);
+
+CREATE INDEX jeopardy_players_winnings ON jeopardy_players (discord_guild_id, winnings DESC);
+
+SELECT diesel_manage_updated_at('jeopardy_players');
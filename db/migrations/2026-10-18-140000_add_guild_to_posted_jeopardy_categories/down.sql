@@ -0,0 +1,3 @@
+DROP INDEX posted_jeopardy_categories_category;
+DROP INDEX posted_jeopardy_categories_guild;
+ALTER TABLE posted_jeopardy_categories DROP COLUMN discord_guild_id;
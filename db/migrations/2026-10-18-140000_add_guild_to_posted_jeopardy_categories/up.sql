@@ -0,0 +1,4 @@
+ALTER TABLE posted_jeopardy_categories ADD COLUMN discord_guild_id BIGINT;
+
+CREATE INDEX posted_jeopardy_categories_guild ON posted_jeopardy_categories (discord_guild_id, jeopardy_category_id);
+CREATE INDEX posted_jeopardy_categories_category ON posted_jeopardy_categories (jeopardy_category_id);
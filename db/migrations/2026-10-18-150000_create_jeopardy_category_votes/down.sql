@@ -0,0 +1,2 @@
+DROP INDEX posted_jeopardy_categories_message;
+DROP TABLE jeopardy_category_votes;
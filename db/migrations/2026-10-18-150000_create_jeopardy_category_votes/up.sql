@@ -0,0 +1,12 @@
+CREATE TABLE jeopardy_category_votes (
+    discord_message_id BIGINT NOT NULL,
+    discord_user_id BIGINT NOT NULL,
+    direction SMALLINT NOT NULL CHECK (direction IN (-1, 1)),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (discord_message_id, discord_user_id)
+);
+
+SELECT diesel_manage_updated_at('jeopardy_category_votes');
+
+CREATE INDEX posted_jeopardy_categories_message ON posted_jeopardy_categories (discord_message_id);
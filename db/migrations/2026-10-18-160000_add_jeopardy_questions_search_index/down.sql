@@ -0,0 +1 @@
+DROP INDEX jeopardy_questions_search;
@@ -0,0 +1,2 @@
+CREATE INDEX jeopardy_questions_search ON jeopardy_questions
+    USING GIN (to_tsvector('english', question || ' ' || answer));
@@ -0,0 +1,8 @@
+DROP TRIGGER count_deleted_jeopardy_questions ON jeopardy_questions;
+DROP TRIGGER count_inserted_jeopardy_questions ON jeopardy_questions;
+DROP FUNCTION count_deleted_jeopardy_questions();
+DROP FUNCTION count_inserted_jeopardy_questions();
+DROP INDEX jeopardy_questions_category;
+DROP INDEX jeopardy_categories_random_key;
+DROP INDEX jeopardy_categories_complete;
+ALTER TABLE jeopardy_categories DROP COLUMN random_key, DROP COLUMN question_count;
@@ -0,0 +1,52 @@
+-- `question_count` is kept up to date by triggers on jeopardy_questions so that complete
+-- categories can be found without counting their questions. `random_key` gives each category
+-- a fixed random position, so picking one is an index lookup instead of sorting the table.
+ALTER TABLE jeopardy_categories
+    ADD COLUMN question_count INT NOT NULL DEFAULT 0,
+    ADD COLUMN random_key DOUBLE PRECISION NOT NULL DEFAULT random();
+
+UPDATE jeopardy_categories
+SET question_count = counts.question_count
+FROM (
+    SELECT jeopardy_category_id, COUNT(*) AS question_count FROM jeopardy_questions
+    GROUP BY jeopardy_category_id
+) counts
+WHERE counts.jeopardy_category_id = jeopardy_categories.id;
+
+CREATE INDEX jeopardy_categories_complete ON jeopardy_categories (random_key) WHERE question_count = 5;
+CREATE INDEX jeopardy_categories_random_key ON jeopardy_categories (random_key);
+CREATE INDEX jeopardy_questions_category ON jeopardy_questions (jeopardy_category_id);
+
+CREATE FUNCTION count_inserted_jeopardy_questions() RETURNS trigger AS $$
+BEGIN
+    UPDATE jeopardy_categories
+    SET question_count = jeopardy_categories.question_count + counts.question_count
+    FROM (
+        SELECT jeopardy_category_id, COUNT(*) AS question_count FROM inserted
+        GROUP BY jeopardy_category_id
+    ) counts
+    WHERE counts.jeopardy_category_id = jeopardy_categories.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION count_deleted_jeopardy_questions() RETURNS trigger AS $$
+BEGIN
+    UPDATE jeopardy_categories
+    SET question_count = jeopardy_categories.question_count - counts.question_count
+    FROM (
+        SELECT jeopardy_category_id, COUNT(*) AS question_count FROM deleted
+        GROUP BY jeopardy_category_id
+    ) counts
+    WHERE counts.jeopardy_category_id = jeopardy_categories.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER count_inserted_jeopardy_questions AFTER INSERT ON jeopardy_questions
+    REFERENCING NEW TABLE AS inserted
+    FOR EACH STATEMENT EXECUTE FUNCTION count_inserted_jeopardy_questions();
+
+CREATE TRIGGER count_deleted_jeopardy_questions AFTER DELETE ON jeopardy_questions
+    REFERENCING OLD TABLE AS deleted
+    FOR EACH STATEMENT EXECUTE FUNCTION count_deleted_jeopardy_questions();
@@ -0,0 +1,2 @@
+DROP INDEX jeopardy_questions_natural_key;
+DROP INDEX jeopardy_categories_natural_key;
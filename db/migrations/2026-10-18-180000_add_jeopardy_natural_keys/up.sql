@@ -0,0 +1,21 @@
+-- The seeder upserts against these so that it can be re-run and load newer exports. Questions
+-- that were seeded more than once are merged into the first of them, answers and all.
+CREATE TEMPORARY TABLE duplicate_jeopardy_questions ON COMMIT DROP AS
+SELECT id, original_id
+FROM (
+    SELECT id, first_value(id) OVER (PARTITION BY jeopardy_category_id, question ORDER BY id) AS original_id
+    FROM jeopardy_questions
+) questions
+WHERE id <> original_id;
+
+UPDATE jeopardy_game_answers
+SET jeopardy_question_id = duplicate.original_id
+FROM duplicate_jeopardy_questions duplicate
+WHERE jeopardy_game_answers.jeopardy_question_id = duplicate.id;
+
+DELETE FROM jeopardy_questions
+USING duplicate_jeopardy_questions duplicate
+WHERE jeopardy_questions.id = duplicate.id;
+
+CREATE UNIQUE INDEX jeopardy_categories_natural_key ON jeopardy_categories (jeopardy_show_number, round, name);
+CREATE UNIQUE INDEX jeopardy_questions_natural_key ON jeopardy_questions (jeopardy_category_id, question);
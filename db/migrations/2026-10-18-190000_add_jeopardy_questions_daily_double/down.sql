@@ -0,0 +1,3 @@
+ALTER TABLE jeopardy_questions
+    DROP COLUMN raw_value,
+    DROP COLUMN is_daily_double;
@@ -0,0 +1,26 @@
+-- `raw_value` keeps the value as the archive lists it. Daily Doubles aren't marked in the
+-- archive, but they're listed with the contestant's wager, which rarely lands on one of the
+-- round's values. The values on the board were doubled on 2001-11-26.
+ALTER TABLE jeopardy_questions
+    ADD COLUMN raw_value TEXT NOT NULL DEFAULT '',
+    ADD COLUMN is_daily_double BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE jeopardy_questions
+SET raw_value = COALESCE('$' || to_char(jeopardy_questions.value, 'FM999,999'), 'None'),
+    is_daily_double = COALESCE(
+        jeopardy_questions.value NOT IN (
+            SELECT steps.step * generate_series(1, 5)
+        ),
+        FALSE
+    )
+FROM jeopardy_categories
+JOIN jeopardy_shows ON jeopardy_shows.show_number = jeopardy_categories.jeopardy_show_number
+CROSS JOIN LATERAL (
+    SELECT CASE jeopardy_categories.round
+        WHEN 'Jeopardy!' THEN 100
+        WHEN 'Double Jeopardy!' THEN 200
+    END * CASE WHEN jeopardy_shows.air_date >= '2001-11-26' THEN 2 ELSE 1 END AS step
+) steps
+WHERE jeopardy_categories.id = jeopardy_questions.jeopardy_category_id;
+
+ALTER TABLE jeopardy_questions ALTER COLUMN raw_value DROP DEFAULT;
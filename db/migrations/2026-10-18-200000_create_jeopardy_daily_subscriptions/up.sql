@@ -0,0 +1,17 @@
+-- Channels that get a Clue of the Day. `post_at` is a time of day in UTC. Once a clue has
+-- been posted, `reveal_message_id` and `reveal_question_id` hold it until its answer is
+-- revealed at `reveal_at`.
+CREATE TABLE jeopardy_daily_subscriptions (
+    discord_channel_id BIGINT PRIMARY KEY,
+    discord_guild_id BIGINT,
+    post_at TIME NOT NULL,
+    reveal_after_hours INT NOT NULL CHECK (reveal_after_hours > 0),
+    last_posted_on DATE,
+    reveal_message_id BIGINT,
+    reveal_question_id UUID REFERENCES jeopardy_questions (id) ON DELETE SET NULL,
+    reveal_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+SELECT diesel_manage_updated_at('jeopardy_daily_subscriptions');
@@ -0,0 +1,12 @@
+-- Hunt the Wumpus games in progress, one per player. `state` is the whole game as the bot
+-- serializes it, and games that haven't been played for a while are expired by `updated_at`.
+CREATE TABLE wumpus_games (
+    discord_user_id BIGINT PRIMARY KEY,
+    state JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX wumpus_games_updated_at ON wumpus_games (updated_at);
+
+SELECT diesel_manage_updated_at('wumpus_games');
@@ -0,0 +1 @@
+ALTER TABLE wumpus_games RENAME COLUMN discord_id TO discord_user_id;
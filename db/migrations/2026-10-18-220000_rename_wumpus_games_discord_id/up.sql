@@ -0,0 +1,3 @@
+-- Channels have shared Hunt the Wumpus games too. Discord never gives a channel and a user the
+-- same id, so one key does for both.
+ALTER TABLE wumpus_games RENAME COLUMN discord_user_id TO discord_id;
@@ -0,0 +1 @@
+DROP TABLE wumpus_results;
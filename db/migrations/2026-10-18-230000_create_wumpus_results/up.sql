@@ -0,0 +1,18 @@
+-- How each finished Hunt the Wumpus game went for each of its players. `outcome` is the state
+-- the game ended in for them: Won, GameOverShot, GameOverWumpus, GameOverPit or GameOverArrows,
+-- or Lost if someone else won a versus game.
+CREATE TABLE wumpus_results (
+    id UUID PRIMARY KEY,
+    discord_user_id BIGINT NOT NULL,
+    discord_guild_id BIGINT,
+    outcome TEXT NOT NULL,
+    moves INT NOT NULL,
+    arrows_used INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX wumpus_results_discord_user_id ON wumpus_results (discord_user_id);
+CREATE INDEX wumpus_results_discord_guild_id ON wumpus_results (discord_guild_id, discord_user_id);
+
+SELECT diesel_manage_updated_at('wumpus_results');
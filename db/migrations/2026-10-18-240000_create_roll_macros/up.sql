@@ -0,0 +1,12 @@
+-- Dice macros saved with `/roll save` and `/roll share`. Macros are kept under the id of the user
+-- or the guild they belong to, which Discord never gives to both.
+CREATE TABLE roll_macros (
+    discord_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    expression TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (discord_id, name)
+);
+
+SELECT diesel_manage_updated_at('roll_macros');
@@ -0,0 +1 @@
+ALTER TABLE roll_macros DROP COLUMN created_by;
@@ -0,0 +1,5 @@
+-- Who saved each macro, so that only they (or the server's managers) can replace or unshare a
+-- shared one. Nobody knows who shared the existing ones, so they're left to the managers.
+ALTER TABLE roll_macros ADD COLUMN created_by BIGINT;
+UPDATE roll_macros SET created_by = discord_id;
+ALTER TABLE roll_macros ALTER COLUMN created_by SET NOT NULL;
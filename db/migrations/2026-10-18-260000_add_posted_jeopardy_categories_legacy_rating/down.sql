@@ -0,0 +1 @@
+ALTER TABLE posted_jeopardy_categories DROP COLUMN legacy_rating;
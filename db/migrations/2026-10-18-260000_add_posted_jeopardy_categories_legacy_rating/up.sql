@@ -0,0 +1,11 @@
+-- Ratings used to be counted up and down as reactions came and went, without knowing who
+-- reacted. Whatever they add up to beyond the recorded votes is kept, so that recounting a post's
+-- votes doesn't throw it away.
+ALTER TABLE posted_jeopardy_categories ADD COLUMN legacy_rating INTEGER NOT NULL DEFAULT 0;
+
+UPDATE posted_jeopardy_categories
+SET legacy_rating = rating - COALESCE((
+    SELECT SUM(direction)
+    FROM jeopardy_category_votes
+    WHERE jeopardy_category_votes.discord_message_id = posted_jeopardy_categories.discord_message_id
+), 0);
@@ -0,0 +1 @@
+-- The keys were random to begin with, so there's nothing to undo.
@@ -0,0 +1,10 @@
+-- Categories are picked by the first `random_key` after a random point, which favours the ones
+-- after wide gaps. Evenly spaced keys in a random order give every category the same chance.
+-- The seeder spreads them out again after loading new categories.
+UPDATE jeopardy_categories
+SET random_key = spread.random_key
+FROM (
+    SELECT id, (ROW_NUMBER() OVER (ORDER BY random()) - 0.5) / COUNT(*) OVER () AS random_key
+    FROM jeopardy_categories
+) spread
+WHERE spread.id = jeopardy_categories.id;
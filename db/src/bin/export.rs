@@ -0,0 +1,49 @@
+//! Exports categories and their questions from the database.
+//!
+//! `export FORMAT SELECTION [OUTPUT]` writes to OUTPUT, or to stdout when it's not given.
+//! FORMAT is `csv`, `json` or `anki`, and SELECTION is `top`, `search:TEXT` or `show:NUMBER`.
+//! CSV and JSON exports can be seeded again, and Anki exports can be imported as notes with
+//! File > Import.
+
+use db::export::Selection;
+
+/// The most categories exported by `top` and `search:`.
+const LIMIT: i64 = 500;
+
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if dotenv::dotenv().is_err() {
+        eprintln!("Unable to load .env file!");
+    }
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (format, selection, output) = match args.as_slice() {
+        [format, selection] => (format, selection, None),
+        [format, selection, output] => (format, selection, Some(output)),
+        _ => return Err("usage: export csv|json|anki top|search:TEXT|show:NUMBER [OUTPUT]".into()),
+    };
+    let format = format.parse::<jeopardy::ExportFormat>()?;
+    let selection = match selection.split_once(':') {
+        None if selection == "top" => Selection::TopRated,
+        Some(("search", text)) => Selection::Search(text.to_owned()),
+        Some(("show", show_number)) => Selection::Show(show_number.parse()?),
+        _ => return Err(format!("Unknown selection: {}", selection).into()),
+    };
+
+    let mut db = db::DB::new(db::DB::env_url()?)?;
+    let categories = db.export_jeopardy_categories(&selection, LIMIT)?;
+    let records = db::export::to_records(&categories)?;
+    match output {
+        Some(path) => {
+            let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            jeopardy::write_records(&records, format, file)?;
+        }
+        None => jeopardy::write_records(&records, format, std::io::stdout().lock())?,
+    }
+    eprintln!(
+        "Exported {} categories, {} questions.",
+        categories.len(),
+        records.len()
+    );
+
+    Ok(())
+}
@@ -1,102 +1,171 @@
-use db::models::{NewJeopardyCategory, NewJeopardyShow};
+//! Loads exports of the J! Archive into the database.
+//!
+//! `seed [FILE]...` loads each file in turn, or the bundled `JEOPARDY.csv` when no files are
+//! given. Shows, categories and questions that are already in the database are updated in
+//! place, so seeding can be re-run and newer seasons can be loaded on top of older ones.
+
+use db::models::{NewJeopardyCategory, NewJeopardyQuestion, NewJeopardyShow};
+use db::schema::{jeopardy_categories as jc, jeopardy_questions as jq, jeopardy_shows as js};
 use diesel::prelude::*;
+use diesel::upsert::excluded;
+use std::collections::{HashMap, HashSet};
+
+/// Each chunk of shows is written in its own transaction.
+const SHOWS_PER_CHUNK: usize = 100;
+/// Postgres' limit on the bind parameters in one statement.
+const MAX_BIND_PARAMETERS: usize = 65535;
+/// Categories take a bind parameter for each of their 4 columns.
+const CATEGORIES_PER_INSERT: usize = MAX_BIND_PARAMETERS / 4;
+/// Questions take a bind parameter for each of their 7 columns.
+const QUESTIONS_PER_INSERT: usize = MAX_BIND_PARAMETERS / 7;
+/// Categories are picked by the first `random_key` after a random point, so evenly spaced keys
+/// in a random order give every category the same chance. New categories get keys at random.
+const SPREAD_RANDOM_KEYS: &str = "
+    UPDATE jeopardy_categories
+    SET random_key = spread.random_key
+    FROM (
+        SELECT id, (ROW_NUMBER() OVER (ORDER BY random()) - 0.5) / COUNT(*) OVER () AS random_key
+        FROM jeopardy_categories
+    ) spread
+    WHERE spread.id = jeopardy_categories.id";
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    dotenv::dotenv().unwrap();
+    if dotenv::dotenv().is_err() {
+        eprintln!("Unable to load .env file!");
+    }
 
-    let uri = std::env::var("DATABASE_URL").unwrap();
-    let mut connection = diesel::pg::PgConnection::establish(&uri).unwrap();
+    let uri = std::env::var("DATABASE_URL")?;
+    let mut connection = diesel::pg::PgConnection::establish(&uri)?;
 
-    jeopardy_seed(&mut connection);
+    let paths = std::env::args().skip(1).collect::<Vec<_>>();
+    if paths.is_empty() {
+        jeopardy_seed(&mut connection, "JEOPARDY.csv", &jeopardy::Jeopardy::new()?)?;
+    }
+    for path in paths {
+        let j = jeopardy::Jeopardy::from_path(&path)?;
+        jeopardy_seed(&mut connection, &path, &j)?;
+    }
+    diesel::sql_query(SPREAD_RANDOM_KEYS).execute(&mut connection)?;
 
     Ok(())
 }
 
-fn jeopardy_seed(conn: &mut PgConnection) {
-    let j = jeopardy::Jeopardy::new().unwrap();
-    let shows = j
-        .shows()
+fn jeopardy_seed(
+    conn: &mut PgConnection,
+    name: &str,
+    j: &jeopardy::Jeopardy,
+) -> Result<(), diesel::result::Error> {
+    let mut shows = j.shows().to_vec();
+    shows.sort_unstable();
+
+    let mut seeded_shows = 0;
+    let mut seeded_questions = 0;
+    for chunk in shows.chunks(SHOWS_PER_CHUNK) {
+        seeded_questions += conn.transaction(|conn| seed_shows(conn, j, chunk))?;
+        seeded_shows += chunk.len();
+        println!(
+            "{}: seeded {} / {} shows, {} questions.",
+            name,
+            seeded_shows,
+            shows.len(),
+            seeded_questions
+        );
+    }
+    Ok(())
+}
+
+/// Upserts `show_numbers` along with their categories and questions, returning the number of
+/// questions written.
+fn seed_shows(
+    conn: &mut PgConnection,
+    j: &jeopardy::Jeopardy,
+    show_numbers: &[u32],
+) -> Result<usize, diesel::result::Error> {
+    let records = show_numbers
+        .iter()
+        .flat_map(|show_number| j.categories(show_number).into_iter().flatten())
+        .collect::<Vec<_>>();
+
+    let shows = show_numbers
         .iter()
-        .map(|show_number| {
-            let air_date = j
-                .categories(show_number)
-                .unwrap()
-                .first()
-                .map(|record| record.air_date)
-                .unwrap();
-            let show_number = *show_number as i32;
-            let (year, ordinal) = air_date.to_ordinal_date();
+        .filter_map(|show_number| j.categories(show_number)?.first())
+        .map(|record| {
+            let (year, ordinal) = record.air_date.to_ordinal_date();
             NewJeopardyShow {
-                show_number,
-                air_date: chrono::NaiveDate::from_yo(year as _, ordinal as _),
+                show_number: record.show_number as i32,
+                air_date: chrono::NaiveDate::from_yo_opt(year, ordinal as u32)
+                    .expect("air date out of range"),
             }
         })
         .collect::<Vec<_>>();
-
-    let mut categories = vec![];
-    let mut questions = vec![];
-    for show_number in j.shows() {
-        let mut seen = std::collections::HashMap::new();
-        for question in j.categories(show_number).unwrap() {
-            let jeopardy_category_id = match seen.get(question.category.as_str()).copied() {
-                Some(jeopardy_category_id) => jeopardy_category_id,
-                None => {
-                    let id = uuid::Uuid::new_v4();
-                    seen.insert(question.category.as_str(), id);
-                    categories.push(NewJeopardyCategory {
-                        id,
-                        jeopardy_show_number: *show_number as _,
-                        name: question.category.as_str(),
-                        round: question.round.as_str(),
-                    });
-                    id
-                }
-            };
-            questions.push({
-                // mostly this fails on "None"
-                let value = question
-                    .value
-                    .as_str()
-                    .trim()
-                    .replace('$', "")
-                    .replace(',', "")
-                    .parse()
-                    .ok();
-                db::models::NewJeopardyQuestion {
-                    id: uuid::Uuid::new_v4(),
-                    jeopardy_category_id,
-                    value,
-                    question: question.question.as_str(),
-                    answer: question.answer.as_str(),
-                }
-            });
-        }
-    }
-
-    diesel::insert_into(db::schema::jeopardy_shows::dsl::jeopardy_shows)
+    diesel::insert_into(js::table)
         .values(&shows)
-        .execute(conn)
-        .unwrap();
-    for (index, category_chunk) in categories.chunks(10000).enumerate() {
-        diesel::insert_into(db::schema::jeopardy_categories::dsl::jeopardy_categories)
-            .values(category_chunk)
-            .execute(conn)
-            .unwrap();
-        println!(
-            "Seed category cluster {} / {}.",
-            index,
-            categories.len() / 10000
+        .on_conflict(js::show_number)
+        .do_update()
+        .set(js::air_date.eq(excluded(js::air_date)))
+        .execute(conn)?;
+
+    let mut seen = HashSet::new();
+    let categories = records
+        .iter()
+        .filter(|record| seen.insert((record.show_number, &record.round, &record.category)))
+        .map(|record| NewJeopardyCategory {
+            id: uuid::Uuid::new_v4(),
+            jeopardy_show_number: record.show_number as i32,
+            name: record.category.as_str(),
+            round: record.round.as_str(),
+        })
+        .collect::<Vec<_>>();
+    // existing categories keep their ids, so look them all up again
+    let mut category_ids = HashMap::new();
+    for chunk in categories.chunks(CATEGORIES_PER_INSERT) {
+        let ids = diesel::insert_into(jc::table)
+            .values(chunk)
+            .on_conflict((jc::jeopardy_show_number, jc::round, jc::name))
+            .do_update()
+            .set(jc::updated_at.eq(diesel::dsl::now))
+            .returning((jc::jeopardy_show_number, jc::round, jc::name, jc::id))
+            .get_results::<(i32, String, String, uuid::Uuid)>(conn)?;
+        category_ids.extend(
+            ids.into_iter()
+                .map(|(show_number, round, name, id)| ((show_number, round, name), id)),
         );
     }
-    for (index, questions_chunk) in questions.chunks(10000).enumerate() {
-        diesel::insert_into(db::schema::jeopardy_questions::dsl::jeopardy_questions)
-            .values(questions_chunk)
-            .execute(conn)
-            .unwrap();
-        println!(
-            "Seed questions cluster {} / {}.",
-            index,
-            questions.len() / 10000
-        );
+
+    let mut seen = HashSet::new();
+    let questions = records
+        .iter()
+        .filter_map(|record| {
+            let key = (
+                record.show_number as i32,
+                record.round.clone(),
+                record.category.clone(),
+            );
+            let jeopardy_category_id = category_ids[&key];
+            seen.insert((jeopardy_category_id, &record.question))
+                .then(|| NewJeopardyQuestion {
+                    id: uuid::Uuid::new_v4(),
+                    jeopardy_category_id,
+                    value: record.value(),
+                    question: record.question.as_str(),
+                    answer: record.answer.as_str(),
+                    raw_value: record.value.as_str(),
+                    is_daily_double: record.is_daily_double(),
+                })
+        })
+        .collect::<Vec<_>>();
+    for chunk in questions.chunks(QUESTIONS_PER_INSERT) {
+        diesel::insert_into(jq::table)
+            .values(chunk)
+            .on_conflict((jq::jeopardy_category_id, jq::question))
+            .do_update()
+            .set((
+                jq::value.eq(excluded(jq::value)),
+                jq::answer.eq(excluded(jq::answer)),
+                jq::raw_value.eq(excluded(jq::raw_value)),
+                jq::is_daily_double.eq(excluded(jq::is_daily_double)),
+            ))
+            .execute(conn)?;
     }
+    Ok(questions.len())
 }
@@ -0,0 +1,79 @@
+//! Exporting categories with their questions, in the formats written by
+//! [`jeopardy::write_records`].
+
+use crate::models::{JeopardyCategory, JeopardyQuestion, JeopardyShow};
+use jeopardy::Record;
+
+pub type Category = (JeopardyShow, JeopardyCategory, Vec<JeopardyQuestion>);
+
+/// Which categories to export.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Categories that have been voted up overall, best first.
+    TopRated,
+    /// Categories with a clue matching the text, which is parsed like a web search.
+    Search(String),
+    /// Every category played in the show with this number.
+    Show(i32),
+}
+
+impl Selection {
+    /// A short name for the selection, fit for a file name.
+    pub fn name(&self) -> String {
+        match self {
+            Selection::TopRated => "top-rated".to_owned(),
+            Selection::Search(text) => {
+                let words = text
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .collect::<Vec<_>>();
+                format!("search-{}", words.join("-").to_lowercase())
+            }
+            Selection::Show(show_number) => format!("show-{}", show_number),
+        }
+    }
+}
+
+/// Turns categories back into the records they were seeded from, so that an export can be
+/// seeded again.
+pub fn to_records(categories: &[Category]) -> eyre::Result<Vec<Record>> {
+    let mut records = vec![];
+    for (show, category, questions) in categories {
+        let air_date = air_date(show.air_date)?;
+        for question in questions {
+            records.push(Record {
+                show_number: show.show_number as u32,
+                air_date,
+                round: category.round.clone(),
+                category: category.name.clone(),
+                value: question.raw_value.clone(),
+                question: question.question.clone(),
+                answer: question.answer.clone(),
+            });
+        }
+    }
+    Ok(records)
+}
+
+fn air_date(date: chrono::NaiveDate) -> eyre::Result<time::Date> {
+    use chrono::Datelike;
+    Ok(time::Date::from_ordinal_date(
+        date.year(),
+        date.ordinal() as u16,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_names() {
+        assert_eq!(Selection::TopRated.name(), "top-rated");
+        assert_eq!(
+            Selection::Search("\"Vice President\" -Cheney".to_owned()).name(),
+            "search-vice-president-cheney"
+        );
+        assert_eq!(Selection::Show(4680).name(), "show-4680");
+    }
+}
@@ -0,0 +1,104 @@
+/// Narrows down which categories [`crate::DB::filtered_jeopardy_category`] can choose from.
+///
+/// ```ignore
+/// let filter = CategoryFilter::new()
+///     .round(jeopardy::Round::DoubleJeopardy)
+///     .from_year(1995)
+///     .to_year(2005);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CategoryFilter {
+    pub(crate) complete: bool,
+    pub(crate) round: Option<jeopardy::Round>,
+    pub(crate) from_year: Option<i32>,
+    pub(crate) to_year: Option<i32>,
+    pub(crate) min_value: Option<i32>,
+    pub(crate) category_contains: Option<String>,
+    pub(crate) unposted_in: Option<u64>,
+    pub(crate) without_downvoted: bool,
+    pub(crate) rated: bool,
+}
+
+impl CategoryFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only categories with a full set of clues for their round: five, or one in Final
+    /// Jeopardy!.
+    pub fn complete(mut self) -> Self {
+        self.complete = true;
+        self
+    }
+
+    pub fn round(mut self, round: jeopardy::Round) -> Self {
+        self.round = Some(round);
+        self
+    }
+
+    /// Only shows that aired in or after `year`.
+    pub fn from_year(mut self, year: i32) -> Self {
+        self.from_year = Some(year);
+        self
+    }
+
+    /// Only shows that aired in or before `year`.
+    pub fn to_year(mut self, year: i32) -> Self {
+        self.to_year = Some(year);
+        self
+    }
+
+    /// Only categories whose clues are all worth at least `value`.
+    pub fn min_value(mut self, value: i32) -> Self {
+        self.min_value = Some(value);
+        self
+    }
+
+    /// Only categories with `text` somewhere in their name, ignoring case.
+    pub fn category_contains<S: Into<String>>(mut self, text: S) -> Self {
+        self.category_contains = Some(text.into());
+        self
+    }
+
+    /// Skips categories that have already been posted in the guild.
+    pub fn unposted_in(mut self, discord_guild_id: u64) -> Self {
+        self.unposted_in = Some(discord_guild_id);
+        self
+    }
+
+    /// Skips categories whose posts have been voted down overall.
+    pub fn without_downvoted(mut self) -> Self {
+        self.without_downvoted = true;
+        self
+    }
+
+    /// Only categories whose posts have been voted up overall, chosen with a probability
+    /// proportional to their rating.
+    pub fn rated(mut self) -> Self {
+        self.rated = true;
+        self
+    }
+
+    /// The `ILIKE` pattern for [`CategoryFilter::category_contains`].
+    pub(crate) fn name_pattern(&self) -> Option<String> {
+        self.category_contains.as_ref().map(|text| {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_pattern_escapes_wildcards() {
+        let filter = CategoryFilter::new().category_contains("100% _sure_");
+        assert_eq!(filter.name_pattern().unwrap(), "%100\\% \\_sure\\_%");
+        assert_eq!(CategoryFilter::new().name_pattern(), None);
+    }
+}
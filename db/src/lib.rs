@@ -1,5 +1,8 @@
+pub mod export;
+pub mod filter;
 pub mod models;
 pub mod schema;
+pub mod source;
 
 use diesel::prelude::*;
 use diesel::r2d2::ManageConnection;
@@ -24,25 +27,198 @@ impl DB {
         Ok(Self { pool })
     }
 
+    /// A random category with a full set of clues for its round.
     pub fn random_jeopardy_category(
         &mut self,
     ) -> eyre::Result<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)> {
+        let filter = filter::CategoryFilter::new().complete();
+        self.filtered_jeopardy_category(&filter)?
+            .ok_or_else(|| eyre::eyre!("No complete categories."))
+    }
+
+    /// A random category matching `filter`, or nothing when no category matches.
+    ///
+    /// Categories are picked by looking up the first `random_key` after a random point, so
+    /// finding one is a single index scan rather than sorting every match. The seeder spreads
+    /// the keys out evenly, so that no category sits after a wider gap than the rest.
+    pub fn filtered_jeopardy_category(
+        &mut self,
+        filter: &filter::CategoryFilter,
+    ) -> eyre::Result<Option<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)>> {
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Double};
         let ref mut conn = self.pool.get()?;
-        let category = schema::jeopardy_categories::table
-            .order(random())
-            .first::<models::JeopardyCategory>(conn)?;
-        let questions = models::JeopardyQuestion::belonging_to(&category)
-            .load::<models::JeopardyQuestion>(conn)?;
-        Ok((category, questions))
+        let category = if filter.rated {
+            // ordering by random() ^ (1 / rating) samples categories in proportion to their rating
+            Self::filter_categories(filter)?
+                .filter(sql::<Bool>(&format!("{} > 0", RATING)))
+                .order(sql::<Double>(&format!("power(random(), 1.0 / {})", RATING)).desc())
+                .first::<models::JeopardyCategory>(conn)
+                .optional()?
+        } else {
+            Self::keyed_category(conn, filter)?
+        };
+        Self::load_category(conn, category)
+    }
+
+    /// The first category matching `filter` whose `random_key` comes after a random point.
+    fn keyed_category(
+        conn: &mut PgConnection,
+        filter: &filter::CategoryFilter,
+    ) -> eyre::Result<Option<models::JeopardyCategory>> {
+        use diesel::dsl::sql;
+        use diesel::sql_types::Double;
+        use schema::jeopardy_categories as jc;
+        let after = Self::filter_categories(filter)?
+            .filter(jc::random_key.ge(sql::<Double>("(SELECT random())")))
+            .order(jc::random_key)
+            .first::<models::JeopardyCategory>(conn)
+            .optional()?;
+        let category = match after {
+            Some(category) => Some(category),
+            // the random point was past the last match, so wrap around to the first
+            None => Self::filter_categories(filter)?
+                .order(jc::random_key)
+                .first::<models::JeopardyCategory>(conn)
+                .optional()?,
+        };
+        Ok(category)
+    }
+
+    fn filter_categories(
+        filter: &filter::CategoryFilter,
+    ) -> eyre::Result<schema::jeopardy_categories::BoxedQuery<'static, diesel::pg::Pg>> {
+        use diesel::dsl::{not, sql};
+        use diesel::sql_types::Bool;
+        use schema::jeopardy_categories as jc;
+        use schema::jeopardy_questions as jq;
+        use schema::jeopardy_shows as js;
+        use schema::posted_jeopardy_categories as pjc;
+        let mut query = jc::table.into_boxed();
+        if filter.complete {
+            query = match filter.round {
+                Some(round) => query.filter(jc::question_count.eq(round.clues() as i32)),
+                None => {
+                    let last = jeopardy::Round::FinalJeopardy;
+                    query.filter(
+                        jc::round
+                            .eq(last.as_str())
+                            .and(jc::question_count.eq(last.clues() as i32))
+                            .or(jc::round.ne(last.as_str()).and(
+                                jc::question_count.eq(jeopardy::Round::Jeopardy.clues() as i32),
+                            )),
+                    )
+                }
+            };
+        }
+        if let Some(round) = filter.round {
+            query = query.filter(jc::round.eq(round.as_str()));
+        }
+        if filter.from_year.is_some() || filter.to_year.is_some() {
+            let mut shows = js::table.select(js::show_number).into_boxed();
+            if let Some(year) = filter.from_year {
+                shows = shows.filter(js::air_date.ge(first_of_year(year)?));
+            }
+            if let Some(year) = filter.to_year {
+                shows = shows.filter(js::air_date.lt(first_of_year(year + 1)?));
+            }
+            query = query.filter(jc::jeopardy_show_number.eq_any(shows));
+        }
+        if let Some(value) = filter.min_value {
+            let cheaper = jq::table
+                .filter(jq::value.lt(value))
+                .select(jq::jeopardy_category_id);
+            query = query.filter(not(jc::id.eq_any(cheaper)));
+        }
+        if let Some(pattern) = filter.name_pattern() {
+            query = query.filter(jc::name.ilike(pattern));
+        }
+        if let Some(guild_id) = filter.unposted_in {
+            let posted = pjc::table
+                .filter(pjc::discord_guild_id.eq(guild_id as i64))
+                .select(pjc::jeopardy_category_id);
+            query = query.filter(not(jc::id.eq_any(posted)));
+        }
+        if filter.without_downvoted {
+            query = query.filter(sql::<Bool>(NOT_DOWNVOTED));
+        }
+        Ok(query)
+    }
+
+    fn load_category(
+        conn: &mut PgConnection,
+        category: Option<models::JeopardyCategory>,
+    ) -> eyre::Result<Option<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)>> {
+        match category {
+            None => Ok(None),
+            Some(category) => {
+                let questions = models::JeopardyQuestion::belonging_to(&category)
+                    .load::<models::JeopardyQuestion>(conn)?;
+                Ok(Some((category, questions)))
+            }
+        }
+    }
+
+    /// Every category played in `round` of a single random show.
+    pub fn random_jeopardy_show_board(
+        &mut self,
+        round: jeopardy::Round,
+    ) -> eyre::Result<Vec<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)>> {
+        use schema::jeopardy_categories as jc;
+        let ref mut conn = self.pool.get()?;
+        let filter = filter::CategoryFilter::new().round(round);
+        let show_number = match Self::keyed_category(conn, &filter)? {
+            Some(category) => category.jeopardy_show_number,
+            None => return Ok(vec![]),
+        };
+        let categories = jc::table
+            .filter(jc::jeopardy_show_number.eq(show_number))
+            .filter(jc::round.eq(round.as_str()))
+            .load::<models::JeopardyCategory>(conn)?;
+        Self::load_board(conn, categories)
+    }
+
+    /// A board for `round` assembled from complete categories picked at random, each the way
+    /// `filtered_jeopardy_category` picks one.
+    pub fn random_jeopardy_board(
+        &mut self,
+        round: jeopardy::Round,
+    ) -> eyre::Result<Vec<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)>> {
+        let ref mut conn = self.pool.get()?;
+        let filter = filter::CategoryFilter::new().complete().round(round);
+        let mut categories = Vec::<models::JeopardyCategory>::new();
+        // picks can land on the same category twice, so allow a few more than the board needs
+        for _ in 0..round.categories() * 2 {
+            if categories.len() == round.categories() {
+                break;
+            }
+            match Self::keyed_category(conn, &filter)? {
+                None => break,
+                Some(category) if categories.iter().any(|other| other.id == category.id) => {}
+                Some(category) => categories.push(category),
+            }
+        }
+        Self::load_board(conn, categories)
+    }
+
+    fn load_board(
+        conn: &mut PgConnection,
+        categories: Vec<models::JeopardyCategory>,
+    ) -> eyre::Result<Vec<(models::JeopardyCategory, Vec<models::JeopardyQuestion>)>> {
+        let questions = models::JeopardyQuestion::belonging_to(&categories)
+            .load::<models::JeopardyQuestion>(conn)?
+            .grouped_by(&categories);
+        Ok(categories.into_iter().zip(questions).collect())
     }
 
     pub fn record_jeopardy_category_post(
         &mut self,
         category_id: uuid::Uuid,
         discord_message_id: u64,
+        discord_guild_id: Option<u64>,
     ) -> eyre::Result<usize> {
         use schema::posted_jeopardy_categories::dsl::{
-            discord_message_id as dmi, id, jeopardy_category_id as jci,
+            discord_guild_id as dgi, discord_message_id as dmi, id, jeopardy_category_id as jci,
         };
         let ref mut conn = self.pool.get()?;
         let inserted_count = insert_into(schema::posted_jeopardy_categories::table)
@@ -50,6 +226,7 @@ impl DB {
                 id.eq(uuid::Uuid::new_v4()),
                 jci.eq(category_id),
                 dmi.eq(discord_message_id as i64),
+                dgi.eq(discord_guild_id.map(|guild_id| guild_id as i64)),
             ))
             .execute(conn)?;
         Ok(inserted_count)
@@ -70,38 +247,656 @@ impl DB {
             .ok_or_else(|| eyre::eyre!("No category posted for message id: {}", discord_message_id))
     }
 
-    pub fn increment_jeopardy_category_post(
+    /// Records a user's vote on a posted category, replacing any vote they made before, and
+    /// returns the post's new rating. Votes on messages that aren't category posts are ignored.
+    pub fn vote_jeopardy_category_post(
+        &mut self,
+        vote: models::NewJeopardyCategoryVote,
+    ) -> eyre::Result<Option<i32>> {
+        use schema::jeopardy_category_votes as jcv;
+        let ref mut conn = self.pool.get()?;
+        let rating = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            if !Self::is_category_post(conn, vote.discord_message_id)? {
+                return Ok(None);
+            }
+            insert_into(jcv::table)
+                .values(&vote)
+                .on_conflict((jcv::discord_message_id, jcv::discord_user_id))
+                .do_update()
+                .set(&vote)
+                .execute(conn)?;
+            Self::update_jeopardy_category_rating(conn, vote.discord_message_id).map(Some)
+        })?;
+        Ok(rating)
+    }
+
+    /// Withdraws a user's vote in `direction` and returns the post's new rating. A vote the
+    /// other way is left alone.
+    pub fn unvote_jeopardy_category_post(
         &mut self,
         discord_message_id: u64,
-    ) -> eyre::Result<i32> {
-        use schema::posted_jeopardy_categories as pjc;
+        discord_user_id: u64,
+        direction: i16,
+    ) -> eyre::Result<Option<i32>> {
+        use schema::jeopardy_category_votes as jcv;
         let ref mut conn = self.pool.get()?;
-        let predicate = pjc::discord_message_id.eq(discord_message_id as i64);
-        let result = diesel::update(pjc::table)
-            .filter(predicate)
-            .set(pjc::rating.eq(pjc::rating + 1))
+        let discord_message_id = discord_message_id as i64;
+        let rating = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            if !Self::is_category_post(conn, discord_message_id)? {
+                return Ok(None);
+            }
+            diesel::delete(jcv::table)
+                .filter(jcv::discord_message_id.eq(discord_message_id))
+                .filter(jcv::discord_user_id.eq(discord_user_id as i64))
+                .filter(jcv::direction.eq(direction))
+                .execute(conn)?;
+            Self::update_jeopardy_category_rating(conn, discord_message_id).map(Some)
+        })?;
+        Ok(rating)
+    }
+
+    fn is_category_post(
+        conn: &mut PgConnection,
+        discord_message_id: i64,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::posted_jeopardy_categories as pjc;
+        diesel::select(diesel::dsl::exists(
+            pjc::table.filter(pjc::discord_message_id.eq(discord_message_id)),
+        ))
+        .get_result(conn)
+    }
+
+    /// Sets a post's rating to the sum of its votes, on top of whatever it was rated before votes
+    /// were recorded.
+    fn update_jeopardy_category_rating(
+        conn: &mut PgConnection,
+        discord_message_id: i64,
+    ) -> Result<i32, diesel::result::Error> {
+        use schema::jeopardy_category_votes as jcv;
+        use schema::posted_jeopardy_categories as pjc;
+        let rating = jcv::table
+            .filter(jcv::discord_message_id.eq(discord_message_id))
+            .select(diesel::dsl::sum(jcv::direction))
+            .first::<Option<i64>>(conn)?
+            .unwrap_or(0) as i32;
+        let ratings = diesel::update(pjc::table)
+            .filter(pjc::discord_message_id.eq(discord_message_id))
+            .set(pjc::rating.eq(pjc::legacy_rating + rating))
             .returning(pjc::rating)
-            .get_result::<i32>(conn)?;
-        Ok(result)
+            .get_results::<i32>(conn)?;
+        Ok(ratings.first().copied().unwrap_or(rating))
+    }
+
+    pub fn record_jeopardy_game(
+        &mut self,
+        game: models::NewJeopardyGame,
+        answers: &[models::NewJeopardyGameAnswer],
+    ) -> eyre::Result<usize> {
+        let ref mut conn = self.pool.get()?;
+        let inserted_count = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            insert_into(schema::jeopardy_games::table)
+                .values(&game)
+                .execute(conn)?;
+            let inserted_count = insert_into(schema::jeopardy_game_answers::table)
+                .values(answers)
+                .execute(conn)?;
+            if let Some(guild_id) = game.discord_guild_id {
+                Self::update_jeopardy_players(conn, guild_id, answers)?;
+            }
+            Ok(inserted_count)
+        })?;
+        Ok(inserted_count)
+    }
+
+    /// Folds a game's answers, in order, into each player's running stats for the guild. Each
+    /// player's tally for the game is added onto their row in one upsert, so games that finish
+    /// at the same time can't overwrite each other's counts.
+    fn update_jeopardy_players(
+        conn: &mut PgConnection,
+        guild_id: i64,
+        answers: &[models::NewJeopardyGameAnswer],
+    ) -> Result<(), diesel::result::Error> {
+        use diesel::upsert::excluded;
+        use schema::jeopardy_players as jp;
+        let mut players = Vec::<models::NewJeopardyPlayer>::new();
+        for answer in answers {
+            let existing = players
+                .iter()
+                .position(|player| player.discord_user_id == answer.discord_user_id);
+            let index = match existing {
+                Some(index) => index,
+                None => {
+                    let mut player =
+                        models::NewJeopardyPlayer::new(guild_id, answer.discord_user_id);
+                    player.games_played = 1;
+                    players.push(player);
+                    players.len() - 1
+                }
+            };
+            players[index].record(answer.correct, answer.value);
+        }
+        for player in players.iter() {
+            // the streak they came in with runs on until their first miss in this game
+            let leading_streak = answers
+                .iter()
+                .filter(|answer| answer.discord_user_id == player.discord_user_id)
+                .take_while(|answer| answer.correct)
+                .count() as i32;
+            let carried = (player.incorrect == 0) as i32;
+            insert_into(jp::table)
+                .values(player)
+                .on_conflict((jp::discord_guild_id, jp::discord_user_id))
+                .do_update()
+                .set((
+                    jp::games_played.eq(jp::games_played + excluded(jp::games_played)),
+                    jp::correct.eq(jp::correct + excluded(jp::correct)),
+                    jp::incorrect.eq(jp::incorrect + excluded(jp::incorrect)),
+                    jp::winnings.eq(jp::winnings + excluded(jp::winnings)),
+                    jp::current_streak
+                        .eq(jp::current_streak * carried + excluded(jp::current_streak)),
+                    jp::best_streak.eq(greatest(
+                        jp::best_streak,
+                        excluded(jp::best_streak),
+                        jp::current_streak + leading_streak,
+                    )),
+                ))
+                .execute(conn)?;
+        }
+        Ok(())
+    }
+
+    /// The players in a guild with the highest winnings.
+    pub fn jeopardy_leaderboard(
+        &mut self,
+        discord_guild_id: u64,
+        limit: i64,
+    ) -> eyre::Result<Vec<models::JeopardyPlayer>> {
+        use schema::jeopardy_players as jp;
+        let ref mut conn = self.pool.get()?;
+        let players = jp::table
+            .filter(jp::discord_guild_id.eq(discord_guild_id as i64))
+            .order(jp::winnings.desc())
+            .limit(limit)
+            .load::<models::JeopardyPlayer>(conn)?;
+        Ok(players)
+    }
+
+    pub fn jeopardy_player(
+        &mut self,
+        discord_guild_id: u64,
+        discord_user_id: u64,
+    ) -> eyre::Result<Option<models::JeopardyPlayer>> {
+        use schema::jeopardy_players as jp;
+        let ref mut conn = self.pool.get()?;
+        let player = jp::table
+            .find((discord_guild_id as i64, discord_user_id as i64))
+            .first::<models::JeopardyPlayer>(conn)
+            .optional()?;
+        Ok(player)
+    }
+
+    /// Clues whose question or answer match `text`, best matches first. `text` is parsed like a
+    /// web search, so it can contain quoted phrases, `or` and `-excluded` words.
+    pub fn search_questions(
+        &mut self,
+        text: &str,
+        offset: i64,
+        limit: i64,
+    ) -> eyre::Result<
+        Vec<(
+            models::JeopardyQuestion,
+            models::JeopardyCategory,
+            models::JeopardyShow,
+        )>,
+    > {
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Float, Text};
+        use schema::jeopardy_categories as jc;
+        use schema::jeopardy_questions as jq;
+        use schema::jeopardy_shows as js;
+        let ref mut conn = self.pool.get()?;
+        let matches = sql::<Bool>(&format!("{} @@ {}", SEARCH_DOCUMENT, SEARCH_QUERY))
+            .bind::<Text, _>(text)
+            .sql(")");
+        let rank = sql::<Float>(&format!("ts_rank({}, {}", SEARCH_DOCUMENT, SEARCH_QUERY))
+            .bind::<Text, _>(text)
+            .sql("))");
+        let results = jq::table
+            .inner_join(jc::table.inner_join(js::table))
+            .select((jq::all_columns, jc::all_columns, js::all_columns))
+            .filter(matches)
+            .order((rank.desc(), jq::id))
+            .offset(offset)
+            .limit(limit)
+            .load(conn)?;
+        Ok(results)
+    }
+
+    /// Shows that aired on today's month and day in earlier years, oldest first, with the
+    /// categories they played. When there are more than `limit`, a random few are picked.
+    pub fn jeopardy_shows_on_this_day(
+        &mut self,
+        limit: i64,
+    ) -> eyre::Result<Vec<(models::JeopardyShow, Vec<models::JeopardyCategory>)>> {
+        use diesel::dsl::sql;
+        use diesel::sql_types::Bool;
+        use schema::jeopardy_shows as js;
+        let ref mut conn = self.pool.get()?;
+        let mut shows = js::table
+            .filter(sql::<Bool>(ON_THIS_DAY))
+            .order(random())
+            .limit(limit)
+            .load::<models::JeopardyShow>(conn)?;
+        shows.sort_by_key(|show| show.air_date);
+        let categories = models::JeopardyCategory::belonging_to(&shows)
+            .load::<models::JeopardyCategory>(conn)?
+            .grouped_by(&shows);
+        Ok(shows.into_iter().zip(categories).collect())
+    }
+
+    /// Up to `limit` categories picked by `selection`, with their shows and questions. Shows are
+    /// exported in the order the categories were played, and questions from lowest to highest
+    /// value.
+    pub fn export_jeopardy_categories(
+        &mut self,
+        selection: &export::Selection,
+        limit: i64,
+    ) -> eyre::Result<Vec<export::Category>> {
+        use diesel::dsl::sql;
+        use diesel::sql_types::{BigInt, Bool, Text};
+        use schema::jeopardy_categories as jc;
+        use schema::jeopardy_questions as jq;
+        use schema::jeopardy_shows as js;
+        let ref mut conn = self.pool.get()?;
+        let query = jc::table.into_boxed();
+        let query = match selection {
+            export::Selection::TopRated => query
+                .filter(sql::<Bool>(&format!("{} > 0", RATING)))
+                .order((sql::<BigInt>(RATING).desc(), jc::id)),
+            export::Selection::Search(text) => {
+                let matches = sql::<Bool>(&format!("{} @@ {}", SEARCH_DOCUMENT, SEARCH_QUERY))
+                    .bind::<Text, _>(text.clone())
+                    .sql(")");
+                query
+                    .filter(
+                        jc::id.eq_any(jq::table.filter(matches).select(jq::jeopardy_category_id)),
+                    )
+                    .order((jc::jeopardy_show_number, jc::id))
+            }
+            export::Selection::Show(show_number) => query
+                .filter(jc::jeopardy_show_number.eq(show_number))
+                .order(jc::id),
+        };
+        let mut categories = query.limit(limit).load::<models::JeopardyCategory>(conn)?;
+        if let export::Selection::Show(_) = selection {
+            categories.sort_by_key(|category| {
+                jeopardy::Round::ALL
+                    .iter()
+                    .position(|round| round.as_str() == category.round)
+            });
+        }
+        let questions = models::JeopardyQuestion::belonging_to(&categories)
+            .load::<models::JeopardyQuestion>(conn)?
+            .grouped_by(&categories);
+        let shows = js::table
+            .filter(js::show_number.eq_any(categories.iter().map(|c| c.jeopardy_show_number)))
+            .load::<models::JeopardyShow>(conn)?;
+        Ok(categories
+            .into_iter()
+            .zip(questions)
+            .filter_map(|(category, mut questions)| {
+                let show = shows
+                    .iter()
+                    .find(|show| show.show_number == category.jeopardy_show_number)?;
+                questions.sort_by_key(|question| (question.value.is_none(), question.value));
+                Some((show.clone(), category, questions))
+            })
+            .collect())
+    }
+
+    /// Posts a Clue of the Day in the channel every day at `hour`:`minute` UTC, or moves it to
+    /// that time. If the time has already passed today, the first clue is posted tomorrow.
+    pub fn subscribe_jeopardy_daily(
+        &mut self,
+        discord_channel_id: u64,
+        discord_guild_id: Option<u64>,
+        hour: u32,
+        minute: u32,
+        reveal_after_hours: i32,
+    ) -> eyre::Result<()> {
+        use schema::jeopardy_daily_subscriptions as jds;
+        let ref mut conn = self.pool.get()?;
+        let subscription = models::NewJeopardyDailySubscription {
+            discord_channel_id: discord_channel_id as i64,
+            discord_guild_id: discord_guild_id.map(|guild_id| guild_id as i64),
+            post_at: chrono::NaiveTime::from_hms_opt(hour, minute, 0)
+                .ok_or_else(|| eyre::eyre!("Time out of range: {}:{:02}", hour, minute))?,
+            reveal_after_hours,
+        };
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            insert_into(jds::table)
+                .values(&subscription)
+                .on_conflict(jds::discord_channel_id)
+                .do_update()
+                .set(&subscription)
+                .execute(conn)?;
+            diesel::update(jds::table.find(subscription.discord_channel_id))
+                .filter(jds::post_at.le(utc_time_of_day()))
+                .set(jds::last_posted_on.eq(utc_today()))
+                .execute(conn)
+        })?;
+        Ok(())
+    }
+
+    /// Stops posting a Clue of the Day in the channel, returning whether it was subscribed.
+    pub fn unsubscribe_jeopardy_daily(&mut self, discord_channel_id: u64) -> eyre::Result<bool> {
+        use schema::jeopardy_daily_subscriptions as jds;
+        let ref mut conn = self.pool.get()?;
+        let deleted = diesel::delete(jds::table.find(discord_channel_id as i64)).execute(conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Subscriptions whose clue is due and hasn't been posted today. They stay due until
+    /// `set_jeopardy_daily_reveal` records the post, so a clue that couldn't be sent is tried
+    /// again.
+    pub fn due_jeopardy_daily_posts(
+        &mut self,
+    ) -> eyre::Result<Vec<models::JeopardyDailySubscription>> {
+        use schema::jeopardy_daily_subscriptions as jds;
+        let ref mut conn = self.pool.get()?;
+        let due = jds::table
+            .filter(jds::post_at.le(utc_time_of_day()))
+            .filter(jds::last_posted_on.is_distinct_from(utc_today()))
+            .load(conn)?;
+        Ok(due)
     }
 
-    pub fn decrement_jeopardy_category_post(
+    /// Records that today's clue was posted in the channel, and remembers it so that its answer
+    /// can be revealed once the channel's `reveal_after_hours` have passed.
+    pub fn set_jeopardy_daily_reveal(
         &mut self,
+        discord_channel_id: u64,
         discord_message_id: u64,
-    ) -> eyre::Result<i32> {
-        use schema::posted_jeopardy_categories as pjc;
+        question_id: uuid::Uuid,
+    ) -> eyre::Result<()> {
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Nullable, Timestamp};
+        use schema::jeopardy_daily_subscriptions as jds;
         let ref mut conn = self.pool.get()?;
-        let predicate = pjc::discord_message_id.eq(discord_message_id as i64);
-        let result = diesel::update(pjc::table)
-            .filter(predicate)
-            .set(pjc::rating.eq(pjc::rating - 1))
-            .returning(pjc::rating)
-            .get_result::<i32>(conn)?;
-        Ok(result)
+        diesel::update(jds::table.find(discord_channel_id as i64))
+            .set((
+                jds::last_posted_on.eq(utc_today()),
+                jds::reveal_message_id.eq(discord_message_id as i64),
+                jds::reveal_question_id.eq(question_id),
+                jds::reveal_at.eq(sql::<Nullable<Timestamp>>(
+                    "CURRENT_TIMESTAMP + reveal_after_hours * INTERVAL '1 hour'",
+                )),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Clues of the Day whose answers are due, with the channel and message they were posted
+    /// in. They're forgotten as they're returned, so each answer is only revealed once.
+    pub fn take_due_jeopardy_daily_reveals(
+        &mut self,
+    ) -> eyre::Result<Vec<(u64, u64, models::JeopardyQuestion)>> {
+        use diesel::dsl::now;
+        use schema::jeopardy_daily_subscriptions as jds;
+        use schema::jeopardy_questions as jq;
+        let ref mut conn = self.pool.get()?;
+        let due = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            // now() is fixed for the whole transaction, so both statements see the same clues
+            let due = jds::table
+                .inner_join(jq::table)
+                .filter(jds::reveal_at.le(now.nullable()))
+                .select((
+                    jds::discord_channel_id,
+                    jds::reveal_message_id,
+                    jq::all_columns,
+                ))
+                .load::<(i64, Option<i64>, models::JeopardyQuestion)>(conn)?;
+            diesel::update(jds::table.filter(jds::reveal_at.le(now.nullable())))
+                .set((
+                    jds::reveal_message_id.eq(None::<i64>),
+                    jds::reveal_question_id.eq(None::<uuid::Uuid>),
+                    jds::reveal_at.eq(None::<chrono::NaiveDateTime>),
+                ))
+                .execute(conn)?;
+            Ok(due)
+        })?;
+        Ok(due
+            .into_iter()
+            .filter_map(|(channel_id, message_id, question)| {
+                Some((channel_id as u64, message_id? as u64, question))
+            })
+            .collect())
+    }
+
+    /// The Hunt the Wumpus game saved under `discord_id`, a player's or a channel's. Games that
+    /// haven't been saved for `expiry_days` are abandoned, and are deleted before looking.
+    pub fn wumpus_game(
+        &mut self,
+        discord_id: u64,
+        expiry_days: i32,
+    ) -> eyre::Result<Option<serde_json::Value>> {
+        use diesel::dsl::{now, IntervalDsl};
+        use schema::wumpus_games as wg;
+        let ref mut conn = self.pool.get()?;
+        diesel::delete(wg::table.filter(wg::updated_at.lt(now - expiry_days.days())))
+            .execute(conn)?;
+        let state = wg::table
+            .find(discord_id as i64)
+            .select(wg::state)
+            .first(conn)
+            .optional()?;
+        Ok(state)
+    }
+
+    /// Saves a game under `discord_id`, replacing whatever game was saved there before.
+    pub fn save_wumpus_game(
+        &mut self,
+        discord_id: u64,
+        state: &serde_json::Value,
+    ) -> eyre::Result<()> {
+        use diesel::upsert::excluded;
+        use schema::wumpus_games as wg;
+        let ref mut conn = self.pool.get()?;
+        insert_into(wg::table)
+            .values(models::NewWumpusGame {
+                discord_id: discord_id as i64,
+                state,
+            })
+            .on_conflict(wg::discord_id)
+            .do_update()
+            // games expire by when they were last saved, even if nothing about them changed
+            .set((
+                wg::state.eq(excluded(wg::state)),
+                wg::updated_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn delete_wumpus_game(&mut self, discord_id: u64) -> eyre::Result<()> {
+        use schema::wumpus_games as wg;
+        let ref mut conn = self.pool.get()?;
+        diesel::delete(wg::table.find(discord_id as i64)).execute(conn)?;
+        Ok(())
+    }
+
+    pub fn record_wumpus_results(
+        &mut self,
+        results: &[models::NewWumpusResult],
+    ) -> eyre::Result<usize> {
+        let ref mut conn = self.pool.get()?;
+        let inserted_count = insert_into(schema::wumpus_results::table)
+            .values(results)
+            .execute(conn)?;
+        Ok(inserted_count)
+    }
+
+    /// How `discord_user_id`'s Hunt the Wumpus games have ended, wherever they were played,
+    /// most common outcomes first.
+    pub fn wumpus_stats(
+        &mut self,
+        discord_user_id: u64,
+    ) -> eyre::Result<Vec<models::WumpusOutcome>> {
+        use diesel::dsl::count_star;
+        use schema::wumpus_results as wr;
+        let ref mut conn = self.pool.get()?;
+        let outcomes = wr::table
+            .filter(wr::discord_user_id.eq(discord_user_id as i64))
+            .group_by(wr::outcome)
+            .select((
+                wr::outcome,
+                count_star(),
+                diesel::dsl::sum(wr::moves),
+                diesel::dsl::sum(wr::arrows_used),
+                diesel::dsl::min(wr::moves),
+            ))
+            .order(count_star().desc())
+            .load::<(String, i64, Option<i64>, Option<i64>, Option<i32>)>(conn)?;
+        Ok(outcomes
+            .into_iter()
+            .map(
+                |(outcome, games, moves, arrows_used, fewest_moves)| models::WumpusOutcome {
+                    outcome,
+                    games,
+                    moves: moves.unwrap_or_default(),
+                    arrows_used: arrows_used.unwrap_or_default(),
+                    fewest_moves: fewest_moves.unwrap_or_default(),
+                },
+            )
+            .collect())
+    }
+
+    /// The players in a guild who have killed the Wumpus most often, fewest moves to a win
+    /// breaking ties.
+    pub fn wumpus_leaderboard(
+        &mut self,
+        discord_guild_id: u64,
+        limit: i64,
+    ) -> eyre::Result<Vec<models::WumpusLeader>> {
+        use diesel::dsl::{count_star, sql};
+        use diesel::sql_types::{BigInt, Integer, Nullable};
+        use schema::wumpus_results as wr;
+        let ref mut conn = self.pool.get()?;
+        let wins = sql::<BigInt>("count(*) FILTER (WHERE outcome = 'Won')");
+        let fewest_moves = sql::<Nullable<Integer>>("min(moves) FILTER (WHERE outcome = 'Won')");
+        let leaders = wr::table
+            .filter(wr::discord_guild_id.eq(discord_guild_id as i64))
+            .group_by(wr::discord_user_id)
+            .select((
+                wr::discord_user_id,
+                count_star(),
+                wins.clone(),
+                fewest_moves.clone(),
+            ))
+            .order((wins.desc(), fewest_moves.asc().nulls_last()))
+            .limit(limit)
+            .load::<models::WumpusLeader>(conn)?;
+        Ok(leaders)
+    }
+
+    /// The dice macros saved for any of `discord_ids`, by name.
+    pub fn roll_macros(&mut self, discord_ids: &[u64]) -> eyre::Result<Vec<models::RollMacro>> {
+        use schema::roll_macros as rm;
+        let ref mut conn = self.pool.get()?;
+        let discord_ids = discord_ids.iter().map(|id| *id as i64).collect::<Vec<_>>();
+        let macros = rm::table
+            .filter(rm::discord_id.eq_any(discord_ids))
+            .order(rm::name)
+            .load(conn)?;
+        Ok(macros)
+    }
+
+    /// Saves a dice macro under `discord_id` on behalf of `created_by`, replacing the one of the
+    /// same name if there is one.
+    pub fn save_roll_macro(
+        &mut self,
+        discord_id: u64,
+        name: &str,
+        expression: &str,
+        created_by: u64,
+    ) -> eyre::Result<()> {
+        use diesel::upsert::excluded;
+        use schema::roll_macros as rm;
+        let ref mut conn = self.pool.get()?;
+        insert_into(rm::table)
+            .values(models::NewRollMacro {
+                discord_id: discord_id as i64,
+                name,
+                expression,
+                created_by: created_by as i64,
+            })
+            .on_conflict((rm::discord_id, rm::name))
+            .do_update()
+            .set((
+                rm::expression.eq(excluded(rm::expression)),
+                rm::created_by.eq(excluded(rm::created_by)),
+            ))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Deletes a dice macro, returning whether there was one to delete.
+    pub fn delete_roll_macro(&mut self, discord_id: u64, name: &str) -> eyre::Result<bool> {
+        use schema::roll_macros as rm;
+        let ref mut conn = self.pool.get()?;
+        let deleted_count =
+            diesel::delete(rm::table.find((discord_id as i64, name))).execute(conn)?;
+        Ok(deleted_count > 0)
     }
 }
 
 sql_function!(fn random() -> Text);
+sql_function! {
+    /// The largest of three streaks.
+    fn greatest(
+        a: diesel::sql_types::Integer,
+        b: diesel::sql_types::Integer,
+        c: diesel::sql_types::Integer,
+    ) -> diesel::sql_types::Integer;
+}
+
+/// Clue of the Day times are kept in UTC, whatever the database's time zone is.
+fn utc_time_of_day() -> diesel::expression::SqlLiteral<diesel::sql_types::Time> {
+    diesel::dsl::sql("(now() AT TIME ZONE 'UTC')::time")
+}
+
+fn utc_today(
+) -> diesel::expression::SqlLiteral<diesel::sql_types::Nullable<diesel::sql_types::Date>> {
+    diesel::dsl::sql("(now() AT TIME ZONE 'UTC')::date")
+}
+
+fn first_of_year(year: i32) -> eyre::Result<chrono::NaiveDate> {
+    chrono::NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| eyre::eyre!("Year out of range: {}", year))
+}
+
+/// Matches the expression in the `jeopardy_questions_search` index so that it can be used.
+const SEARCH_DOCUMENT: &str =
+    "to_tsvector('english', jeopardy_questions.question || ' ' || jeopardy_questions.answer)";
+const SEARCH_QUERY: &str = "websearch_to_tsquery('english', ";
+
+/// Shows from an earlier year that aired on today's month and day in UTC.
+const ON_THIS_DAY: &str =
+    "to_char(jeopardy_shows.air_date, 'MM-DD') = to_char(now() AT TIME ZONE 'UTC', 'MM-DD')
+    AND jeopardy_shows.air_date < (now() AT TIME ZONE 'UTC')::date";
+
+/// The overall rating of a category across all of its posts.
+const RATING: &str = "(
+    SELECT SUM(rating) FROM posted_jeopardy_categories
+    WHERE posted_jeopardy_categories.jeopardy_category_id = jeopardy_categories.id
+)";
+
+/// Excludes categories that have been voted down overall.
+const NOT_DOWNVOTED: &str = "jeopardy_categories.id NOT IN (
+    SELECT jeopardy_category_id FROM posted_jeopardy_categories
+    GROUP BY jeopardy_category_id
+    HAVING SUM(rating) < 0
+)";
 
 #[cfg(test)]
 mod tests {
@@ -112,7 +907,9 @@ mod tests {
         dotenv::dotenv().unwrap();
         let mut db = DB::new(DB::env_url().unwrap()).unwrap();
         let (category, _questions) = db.random_jeopardy_category().unwrap();
-        let count = db.record_jeopardy_category_post(category.id, 0).unwrap();
+        let count = db
+            .record_jeopardy_category_post(category.id, 0, None)
+            .unwrap();
         assert_eq!(count, 1);
     }
 }
@@ -19,6 +19,8 @@ pub struct JeopardyCategory {
     pub round: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub question_count: i32,
+    pub random_key: f64,
 }
 
 #[derive(Debug, Clone, Queryable, Identifiable, Associations)]
@@ -31,6 +33,9 @@ pub struct JeopardyQuestion {
     pub answer: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    /// The value as it appears in the archive, which for Daily Doubles is the wager.
+    pub raw_value: String,
+    pub is_daily_double: bool,
 }
 
 #[derive(Debug, Clone, Queryable, Identifiable, Associations)]
@@ -42,6 +47,133 @@ pub struct PostedJeopardyCategory {
     pub rating: i32,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub discord_guild_id: Option<i64>,
+    /// What the post was rated before votes were recorded one by one.
+    pub legacy_rating: i32,
+}
+
+/// One user's vote on a posted category. `direction` is 1 for up and -1 for down.
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(primary_key(discord_message_id, discord_user_id), table_name = jeopardy_category_votes)]
+pub struct JeopardyCategoryVote {
+    pub discord_message_id: i64,
+    pub discord_user_id: i64,
+    pub direction: i16,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// A channel that gets a Clue of the Day at `post_at` UTC.
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(primary_key(discord_channel_id), table_name = jeopardy_daily_subscriptions)]
+pub struct JeopardyDailySubscription {
+    pub discord_channel_id: i64,
+    pub discord_guild_id: Option<i64>,
+    pub post_at: chrono::NaiveTime,
+    pub reveal_after_hours: i32,
+    pub last_posted_on: Option<chrono::NaiveDate>,
+    pub reveal_message_id: Option<i64>,
+    pub reveal_question_id: Option<uuid::Uuid>,
+    pub reveal_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(table_name = jeopardy_games)]
+pub struct JeopardyGame {
+    pub id: uuid::Uuid,
+    pub discord_channel_id: i64,
+    pub discord_guild_id: Option<i64>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable, Associations)]
+#[diesel(belongs_to(JeopardyGame), belongs_to(JeopardyQuestion), table_name = jeopardy_game_answers)]
+pub struct JeopardyGameAnswer {
+    pub id: uuid::Uuid,
+    pub jeopardy_game_id: uuid::Uuid,
+    pub jeopardy_question_id: uuid::Uuid,
+    pub discord_user_id: i64,
+    pub response: String,
+    pub correct: bool,
+    pub value: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(primary_key(discord_guild_id, discord_user_id), table_name = jeopardy_players)]
+pub struct JeopardyPlayer {
+    pub discord_guild_id: i64,
+    pub discord_user_id: i64,
+    pub games_played: i32,
+    pub correct: i32,
+    pub incorrect: i32,
+    pub winnings: i64,
+    pub current_streak: i32,
+    pub best_streak: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// A Hunt the Wumpus game in progress. `state` is however the bot serialized the game, and
+/// `discord_id` is the player's id, or the channel's for a game shared by a channel.
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(primary_key(discord_id), table_name = wumpus_games)]
+pub struct WumpusGame {
+    pub discord_id: i64,
+    pub state: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// How a finished Hunt the Wumpus game went for one player.
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(table_name = wumpus_results)]
+pub struct WumpusResult {
+    pub id: uuid::Uuid,
+    pub discord_user_id: i64,
+    pub discord_guild_id: Option<i64>,
+    pub outcome: String,
+    pub moves: i32,
+    pub arrows_used: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// A player's Hunt the Wumpus games that ended the same way.
+#[derive(Debug, Clone)]
+pub struct WumpusOutcome {
+    pub outcome: String,
+    pub games: i64,
+    pub moves: i64,
+    pub arrows_used: i64,
+    pub fewest_moves: i32,
+}
+
+/// A player's Hunt the Wumpus record in a guild.
+#[derive(Debug, Clone, Queryable)]
+pub struct WumpusLeader {
+    pub discord_user_id: i64,
+    pub games: i64,
+    pub wins: i64,
+    /// The fewest moves they've won in.
+    pub fewest_moves: Option<i32>,
+}
+
+/// A dice expression saved under a name, for a user or for everyone in a guild.
+#[derive(Debug, Clone, Queryable, Identifiable)]
+#[diesel(primary_key(discord_id, name))]
+pub struct RollMacro {
+    pub discord_id: i64,
+    pub name: String,
+    pub expression: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    /// The user who saved it, which for a shared macro isn't `discord_id`.
+    pub created_by: i64,
 }
 
 #[derive(Insertable)]
@@ -68,4 +200,112 @@ pub struct NewJeopardyQuestion<'a> {
     pub value: Option<i32>,
     pub question: &'a str,
     pub answer: &'a str,
+    pub raw_value: &'a str,
+    pub is_daily_double: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = jeopardy_games)]
+pub struct NewJeopardyGame {
+    pub id: uuid::Uuid,
+    pub discord_channel_id: i64,
+    pub discord_guild_id: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = jeopardy_game_answers)]
+pub struct NewJeopardyGameAnswer<'a> {
+    pub id: uuid::Uuid,
+    pub jeopardy_game_id: uuid::Uuid,
+    pub jeopardy_question_id: uuid::Uuid,
+    pub discord_user_id: i64,
+    pub response: &'a str,
+    pub correct: bool,
+    pub value: i32,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = jeopardy_category_votes)]
+pub struct NewJeopardyCategoryVote {
+    pub discord_message_id: i64,
+    pub discord_user_id: i64,
+    pub direction: i16,
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = jeopardy_daily_subscriptions)]
+pub struct NewJeopardyDailySubscription {
+    pub discord_channel_id: i64,
+    pub discord_guild_id: Option<i64>,
+    pub post_at: chrono::NaiveTime,
+    pub reveal_after_hours: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = wumpus_games)]
+pub struct NewWumpusGame<'a> {
+    pub discord_id: i64,
+    pub state: &'a serde_json::Value,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = wumpus_results)]
+pub struct NewWumpusResult<'a> {
+    pub id: uuid::Uuid,
+    pub discord_user_id: i64,
+    pub discord_guild_id: Option<i64>,
+    pub outcome: &'a str,
+    pub moves: i32,
+    pub arrows_used: i32,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = roll_macros)]
+pub struct NewRollMacro<'a> {
+    pub discord_id: i64,
+    pub name: &'a str,
+    pub expression: &'a str,
+    pub created_by: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = jeopardy_players)]
+pub struct NewJeopardyPlayer {
+    pub discord_guild_id: i64,
+    pub discord_user_id: i64,
+    pub games_played: i32,
+    pub correct: i32,
+    pub incorrect: i32,
+    pub winnings: i64,
+    pub current_streak: i32,
+    pub best_streak: i32,
+}
+
+impl NewJeopardyPlayer {
+    pub fn new(discord_guild_id: i64, discord_user_id: i64) -> Self {
+        Self {
+            discord_guild_id,
+            discord_user_id,
+            games_played: 0,
+            correct: 0,
+            incorrect: 0,
+            winnings: 0,
+            current_streak: 0,
+            best_streak: 0,
+        }
+    }
+
+    /// Tallies a single response worth `value`.
+    pub fn record(&mut self, correct: bool, value: i32) {
+        if correct {
+            self.correct += 1;
+            self.winnings += value as i64;
+            self.current_streak += 1;
+            self.best_streak = self.best_streak.max(self.current_streak);
+        } else {
+            self.incorrect += 1;
+            self.winnings -= value as i64;
+            self.current_streak = 0;
+        }
+    }
 }
@@ -8,6 +8,72 @@ diesel::table! {
         round -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        question_count -> Int4,
+        random_key -> Float8,
+    }
+}
+
+diesel::table! {
+    jeopardy_category_votes (discord_message_id, discord_user_id) {
+        discord_message_id -> Int8,
+        discord_user_id -> Int8,
+        direction -> Int2,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jeopardy_daily_subscriptions (discord_channel_id) {
+        discord_channel_id -> Int8,
+        discord_guild_id -> Nullable<Int8>,
+        post_at -> Time,
+        reveal_after_hours -> Int4,
+        last_posted_on -> Nullable<Date>,
+        reveal_message_id -> Nullable<Int8>,
+        reveal_question_id -> Nullable<Uuid>,
+        reveal_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jeopardy_game_answers (id) {
+        id -> Uuid,
+        jeopardy_game_id -> Uuid,
+        jeopardy_question_id -> Uuid,
+        discord_user_id -> Int8,
+        response -> Text,
+        correct -> Bool,
+        value -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jeopardy_games (id) {
+        id -> Uuid,
+        discord_channel_id -> Int8,
+        discord_guild_id -> Nullable<Int8>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    jeopardy_players (discord_guild_id, discord_user_id) {
+        discord_guild_id -> Int8,
+        discord_user_id -> Int8,
+        games_played -> Int4,
+        correct -> Int4,
+        incorrect -> Int4,
+        winnings -> Int8,
+        current_streak -> Int4,
+        best_streak -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -20,6 +86,8 @@ diesel::table! {
         answer -> Text,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        raw_value -> Text,
+        is_daily_double -> Bool,
     }
 }
 
@@ -40,16 +108,62 @@ diesel::table! {
         rating -> Int4,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        discord_guild_id -> Nullable<Int8>,
+        legacy_rating -> Int4,
+    }
+}
+
+diesel::table! {
+    roll_macros (discord_id, name) {
+        discord_id -> Int8,
+        name -> Text,
+        expression -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_by -> Int8,
+    }
+}
+
+diesel::table! {
+    wumpus_games (discord_id) {
+        discord_id -> Int8,
+        state -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    wumpus_results (id) {
+        id -> Uuid,
+        discord_user_id -> Int8,
+        discord_guild_id -> Nullable<Int8>,
+        outcome -> Text,
+        moves -> Int4,
+        arrows_used -> Int4,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
 diesel::joinable!(jeopardy_categories -> jeopardy_shows (jeopardy_show_number));
+diesel::joinable!(jeopardy_daily_subscriptions -> jeopardy_questions (reveal_question_id));
+diesel::joinable!(jeopardy_game_answers -> jeopardy_games (jeopardy_game_id));
+diesel::joinable!(jeopardy_game_answers -> jeopardy_questions (jeopardy_question_id));
 diesel::joinable!(jeopardy_questions -> jeopardy_categories (jeopardy_category_id));
 diesel::joinable!(posted_jeopardy_categories -> jeopardy_categories (jeopardy_category_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     jeopardy_categories,
+    jeopardy_category_votes,
+    jeopardy_daily_subscriptions,
+    jeopardy_game_answers,
+    jeopardy_games,
+    jeopardy_players,
     jeopardy_questions,
     jeopardy_shows,
     posted_jeopardy_categories,
+    roll_macros,
+    wumpus_games,
+    wumpus_results,
 );
@@ -0,0 +1,151 @@
+//! Where clues come from. The database is the real thing, but the archive loaded in memory by
+//! the `jeopardy` crate is enough to play without one.
+
+use crate::filter::CategoryFilter;
+use crate::models::{JeopardyCategory, JeopardyQuestion};
+use jeopardy::{Jeopardy, Record, Round};
+
+pub type Category = (JeopardyCategory, Vec<JeopardyQuestion>);
+
+pub trait JeopardySource: Send + Sync {
+    /// A random category matching `filter`, or nothing when no category matches.
+    fn filtered_category(&self, filter: &CategoryFilter) -> eyre::Result<Option<Category>>;
+
+    /// Every category played in `round` of a single random show.
+    fn random_show_board(&self, round: Round) -> eyre::Result<Vec<Category>>;
+
+    /// A board for `round` assembled from categories across different shows.
+    fn random_board(&self, round: Round) -> eyre::Result<Vec<Category>>;
+}
+
+impl JeopardySource for crate::DB {
+    fn filtered_category(&self, filter: &CategoryFilter) -> eyre::Result<Option<Category>> {
+        self.clone().filtered_jeopardy_category(filter)
+    }
+
+    fn random_show_board(&self, round: Round) -> eyre::Result<Vec<Category>> {
+        self.clone().random_jeopardy_show_board(round)
+    }
+
+    fn random_board(&self, round: Round) -> eyre::Result<Vec<Category>> {
+        self.clone().random_jeopardy_board(round)
+    }
+}
+
+/// Nothing is ever posted from the archive, so filters on posts and their ratings have
+/// nothing to rule out, and no category has a rating to be picked by.
+impl JeopardySource for Jeopardy {
+    fn filtered_category(&self, filter: &CategoryFilter) -> eyre::Result<Option<Category>> {
+        if filter.rated {
+            return Ok(None);
+        }
+        let category = self.random_matching(|records| matches(filter, records));
+        Ok(category.map(|records| category_from_records(&records)))
+    }
+
+    fn random_show_board(&self, round: Round) -> eyre::Result<Vec<Category>> {
+        let board = Jeopardy::random_board(self, round).map_err(|err| eyre::eyre!(err))?;
+        Ok(board
+            .iter()
+            .map(|records| category_from_records(records))
+            .collect())
+    }
+
+    fn random_board(&self, round: Round) -> eyre::Result<Vec<Category>> {
+        let board = self
+            .random_mixed_board(round)
+            .map_err(|err| eyre::eyre!(err))?;
+        Ok(board
+            .iter()
+            .map(|records| category_from_records(records))
+            .collect())
+    }
+}
+
+/// The in-memory equivalent of the queries built by `DB::filter_categories`.
+fn matches(filter: &CategoryFilter, records: &[&Record]) -> bool {
+    let first = match records.first() {
+        Some(first) => first,
+        None => return false,
+    };
+    let year = first.air_date.year();
+    let name = first.category.to_lowercase();
+    let complete = || {
+        Round::ALL
+            .iter()
+            .any(|round| first.round == round.as_str() && records.len() == round.clues())
+    };
+    (!filter.complete || complete())
+        && filter
+            .round
+            .iter()
+            .all(|round| first.round == round.as_str())
+        && filter.from_year.iter().all(|from| year >= *from)
+        && filter.to_year.iter().all(|to| year <= *to)
+        && filter.min_value.iter().all(|min| {
+            records
+                .iter()
+                .all(|record| record.value().iter().all(|value| value >= min))
+        })
+        && filter
+            .category_contains
+            .iter()
+            .all(|text| name.contains(&text.to_lowercase()))
+}
+
+/// Records from the archive have no ids, so each category gets fresh ones as it's picked.
+fn category_from_records(records: &[&Record]) -> Category {
+    let now = chrono::Utc::now().naive_utc();
+    let category = JeopardyCategory {
+        id: uuid::Uuid::new_v4(),
+        jeopardy_show_number: records[0].show_number as i32,
+        name: records[0].category.clone(),
+        round: records[0].round.clone(),
+        created_at: now,
+        updated_at: now,
+        question_count: records.len() as i32,
+        random_key: 0.0,
+    };
+    let questions = records
+        .iter()
+        .map(|record| JeopardyQuestion {
+            id: uuid::Uuid::new_v4(),
+            jeopardy_category_id: category.id,
+            value: record.value(),
+            question: record.question.clone(),
+            answer: record.answer.clone(),
+            created_at: now,
+            updated_at: now,
+            raw_value: record.value.clone(),
+            is_daily_double: record.is_daily_double(),
+        })
+        .collect();
+    (category, questions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_filters_categories() {
+        let archive = Jeopardy::new().unwrap();
+        let filter = CategoryFilter::new().round(Round::Jeopardy).complete();
+        let (category, questions) = archive.filtered_category(&filter).unwrap().unwrap();
+        assert_eq!(category.round, Round::Jeopardy.as_str());
+        assert_eq!(questions.len(), 5);
+        assert!(questions
+            .iter()
+            .all(|question| question.jeopardy_category_id == category.id));
+
+        let filter = CategoryFilter::new().round(Round::FinalJeopardy).complete();
+        let (category, questions) = archive.filtered_category(&filter).unwrap().unwrap();
+        assert_eq!(category.round, Round::FinalJeopardy.as_str());
+        assert_eq!(questions.len(), 1);
+
+        let filter = CategoryFilter::new().category_contains("no category is called this");
+        assert!(archive.filtered_category(&filter).unwrap().is_none());
+        let filter = CategoryFilter::new().rated();
+        assert!(archive.filtered_category(&filter).unwrap().is_none());
+    }
+}
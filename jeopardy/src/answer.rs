@@ -0,0 +1,319 @@
+//! Judging free-form responses against the answers in the J! Archive data.
+//!
+//! Answers in the archive are written loosely: "(Alexander) Hamilton", "Ford (or Gerald Ford)",
+//! "a tee (or T)", "Dallas/Fort Worth". Responses are typed by people in a hurry, with or
+//! without the "what is" that the show demands. Both sides are normalized before comparing
+//! and a small number of typos is tolerated depending on the length of the answer.
+
+/// Responses scoring at least this are considered correct by [`is_match`].
+pub const THRESHOLD: f64 = 0.8;
+
+const QUESTION_WORDS: [&str; 5] = ["what", "who", "where", "when", "which"];
+const QUESTION_VERBS: [&str; 4] = ["is", "are", "was", "were"];
+const ARTICLES: [&str; 3] = ["a", "an", "the"];
+
+/// Lowercases, strips punctuation and drops a leading "what is" and article.
+pub fn normalize(s: &str) -> String {
+    let cleaned = s
+        .replace('&', " and ")
+        .chars()
+        .filter(|c| *c != '\'' && *c != '’')
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .flat_map(char::to_lowercase)
+        .collect::<String>();
+    let mut words = cleaned.split_whitespace().collect::<Vec<_>>();
+
+    if words.len() > 2 && QUESTION_WORDS.contains(&words[0]) && QUESTION_VERBS.contains(&words[1]) {
+        words.drain(..2);
+    } else if words.len() > 1 {
+        // "what's" and "who's" lose their apostrophe above
+        let contracted = words[0].strip_suffix('s');
+        if matches!(contracted, Some(word) if QUESTION_WORDS.contains(&word)) {
+            words.remove(0);
+        }
+    }
+    if words.len() > 1 && ARTICLES.contains(&words[0]) {
+        words.remove(0);
+    }
+
+    words.join(" ")
+}
+
+/// Every acceptable form of `answer`, normalized.
+///
+/// "(Alexander) Hamilton" accepts both "Alexander Hamilton" and "Hamilton", while
+/// "Ford (or Gerald Ford)" and "Dallas/Fort Worth" accept either side.
+pub fn alternates(answer: &str) -> Vec<String> {
+    let mut out = vec![];
+    let mut push = |s: &str| {
+        let normalized = normalize(s);
+        if !normalized.is_empty() && !out.contains(&normalized) {
+            out.push(normalized);
+        }
+    };
+
+    let (base, parentheticals) = split_parentheticals(answer);
+    let mut optional = vec![];
+    for inner in parentheticals {
+        let trimmed = inner.trim();
+        let lower = trimmed.to_lowercase();
+        let alternate = ["or ", "accept ", "accept: "]
+            .iter()
+            .find_map(|prefix| lower.starts_with(prefix).then(|| &trimmed[prefix.len()..]));
+        match alternate {
+            Some(alternate) => push(alternate),
+            None => optional.push(trimmed),
+        }
+    }
+
+    // the answer with optional parts filled in and then without them
+    let with_optional = answer
+        .split(['(', ')'])
+        .enumerate()
+        .filter(|(index, part)| index % 2 == 0 || optional.contains(&part.trim()))
+        .map(|(_, part)| part)
+        .collect::<String>();
+    push(&with_optional);
+    push(&base);
+
+    for part in base.split('/') {
+        push(part);
+    }
+    for part in base.split(" or ") {
+        push(part);
+    }
+
+    out
+}
+
+/// How confident we are that `response` is `answer`, between 0 and 1.
+pub fn confidence(response: &str, answer: &str) -> f64 {
+    let response = normalize(response);
+    if response.is_empty() {
+        return 0.0;
+    }
+    alternates(answer)
+        .iter()
+        .map(|alternate| similarity(&response, alternate))
+        .fold(0.0, f64::max)
+}
+
+/// Whether `response` should be accepted for `answer`.
+pub fn is_match(response: &str, answer: &str) -> bool {
+    confidence(response, answer) >= THRESHOLD
+}
+
+/// Similarity of two normalized strings. Short answers have to be spelled exactly; longer
+/// ones are scored by edit distance relative to their length.
+fn similarity(a: &str, b: &str) -> f64 {
+    if a == b {
+        return 1.0;
+    }
+    if a.replace(' ', "") == b.replace(' ', "") {
+        return 0.95;
+    }
+    let len = a.chars().count().max(b.chars().count());
+    if len <= 4 {
+        return 0.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Splits "Ford (or Gerald Ford)" into "Ford" and ["or Gerald Ford"].
+fn split_parentheticals(answer: &str) -> (String, Vec<&str>) {
+    let mut base = String::new();
+    let mut inner = vec![];
+    let mut rest = answer;
+    while let Some(open) = rest.find('(') {
+        base.push_str(&rest[..open]);
+        match rest[open..].find(')') {
+            Some(close) => {
+                inner.push(&rest[open + 1..open + close]);
+                rest = &rest[open + close + 1..];
+            }
+            None => {
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    base.push_str(rest);
+    (base, inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answers written in the styles the archive uses, with parentheticals, alternates and quotes.
+    const COPERNICUS: &str = "Copernicus";
+    const JIM_THORPE: &str = "Jim Thorpe";
+    const MCDONALDS: &str = "McDonald's";
+    const THE_ANT: &str = "the ant";
+    const JOHN_ADAMS: &str = "John Adams";
+    const HAMILTON: &str = "(Alexander) Hamilton";
+    const GEHRIG: &str = "(Lou) Gehrig";
+    const FORD: &str = "Ford (or Gerald Ford)";
+    const HAMLET: &str = "\"Hamlet\"";
+
+    #[test]
+    fn normalizes_question_form() {
+        assert_eq!(normalize("What is Copernicus?"), "copernicus");
+        assert_eq!(normalize("who was JOHN ADAMS"), "john adams");
+        assert_eq!(normalize("what are the ants"), "ants");
+        assert_eq!(normalize("whats an ant"), "ant");
+        assert_eq!(normalize(MCDONALDS), "mcdonalds");
+    }
+
+    #[test]
+    fn keeps_single_word_answers_that_look_like_articles() {
+        assert_eq!(normalize("the"), "the");
+        assert_eq!(normalize("Who"), "who");
+    }
+
+    #[test]
+    fn exact_answers() {
+        assert!(is_match("Copernicus", COPERNICUS));
+        assert!(is_match("what is mcdonalds", MCDONALDS));
+        assert!(is_match("What is an ant?", THE_ANT));
+        assert!(is_match("Hamlet", HAMLET));
+    }
+
+    #[test]
+    fn parenthetical_alternates() {
+        assert!(is_match("Hamilton", HAMILTON));
+        assert!(is_match("Alexander Hamilton", HAMILTON));
+        assert!(is_match("who is gehrig", GEHRIG));
+        assert!(is_match("Gerald Ford", FORD));
+        assert!(is_match("Ford", FORD));
+        assert!(!is_match("Alexander", HAMILTON));
+    }
+
+    #[test]
+    fn tolerates_typos() {
+        assert!(is_match("Copernicous", COPERNICUS));
+        assert!(is_match("jim thrope", JIM_THORPE));
+        assert!(is_match("Jon Adams", JOHN_ADAMS));
+        assert!(!is_match("Jim", JIM_THORPE));
+        assert!(!is_match("Samuel Adams", JOHN_ADAMS));
+    }
+
+    #[test]
+    fn short_answers_must_be_exact() {
+        assert!(is_match("ant", THE_ANT));
+        assert!(!is_match("and", THE_ANT));
+    }
+
+    #[test]
+    fn confidence_is_ordered() {
+        let exact = confidence("Copernicus", COPERNICUS);
+        let typo = confidence("Copernicous", COPERNICUS);
+        let wrong = confidence("Galileo", COPERNICUS);
+        assert_eq!(exact, 1.0);
+        assert!(exact > typo && typo > wrong);
+        assert_eq!(confidence("", COPERNICUS), 0.0);
+    }
+
+    /// Answers of every clue in the archive.
+    fn archive_answers() -> Vec<String> {
+        let collection = crate::Jeopardy::new().unwrap();
+        collection
+            .shows()
+            .iter()
+            .filter_map(|show| collection.categories(show))
+            .flatten()
+            .map(|record| record.answer.clone())
+            .collect()
+    }
+
+    #[test]
+    fn archive_optional_parentheticals() {
+        // "(Alexander) Hamilton"
+        let mut checked = 0;
+        for answer in archive_answers() {
+            let parts = answer
+                .strip_prefix('(')
+                .and_then(|rest| rest.split_once(") "))
+                .filter(|(optional, rest)| {
+                    !optional.to_lowercase().starts_with("or ")
+                        && !optional.to_lowercase().starts_with("accept")
+                        && !optional.contains(['(', ')'])
+                        && !rest.contains(['(', ')'])
+                        && !normalize(rest).is_empty()
+                });
+            if let Some((optional, rest)) = parts {
+                assert!(is_match(rest, &answer), "{:?} for {:?}", rest, answer);
+                let full = format!("{} {}", optional, rest);
+                assert!(is_match(&full, &answer), "{:?} for {:?}", full, answer);
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn archive_or_alternates() {
+        // "Ford (or Gerald Ford)"
+        let mut checked = 0;
+        for answer in archive_answers() {
+            let parts = answer
+                .strip_suffix(')')
+                .and_then(|rest| rest.split_once(" (or "))
+                .filter(|(base, alternate)| {
+                    !base.contains(['(', ')'])
+                        && !alternate.contains(['(', ')'])
+                        && !normalize(base).is_empty()
+                        && !normalize(alternate).is_empty()
+                });
+            if let Some((base, alternate)) = parts {
+                assert!(is_match(base, &answer), "{:?} for {:?}", base, answer);
+                assert!(
+                    is_match(alternate, &answer),
+                    "{:?} for {:?}",
+                    alternate,
+                    answer
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn archive_quoted_titles() {
+        // "Hamlet" in quotes
+        let mut checked = 0;
+        for answer in archive_answers() {
+            let title = answer
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .filter(|title| !title.contains(['"', '(', ')']) && !normalize(title).is_empty());
+            if let Some(title) = title {
+                assert!(is_match(title, &answer), "{:?} for {:?}", title, answer);
+                let response = format!("What is {}?", title);
+                assert!(
+                    is_match(&response, &answer),
+                    "{:?} for {:?}",
+                    response,
+                    answer
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+}
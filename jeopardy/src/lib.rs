@@ -1,23 +1,250 @@
 use rand::prelude::*;
 
-#[derive(Debug, serde::Deserialize)]
+pub mod answer;
+
+/// A single clue. Fields are named as in `JEOPARDY.csv`, but the snake_case names used by the
+/// JSON export of the same archive are accepted too.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct Record {
-    #[serde(rename = "Show Number")]
+    #[serde(
+        rename = "Show Number",
+        alias = "show_number",
+        deserialize_with = "number_or_string"
+    )]
     pub show_number: u32,
-    #[serde(rename = "Air Date")]
+    #[serde(rename = "Air Date", alias = "air_date")]
     pub air_date: time::Date,
-    #[serde(rename = "Round")]
+    #[serde(rename = "Round", alias = "round")]
     pub round: String,
-    #[serde(rename = "Category")]
+    #[serde(rename = "Category", alias = "category")]
     pub category: String,
-    #[serde(rename = "Value")]
+    #[serde(
+        rename = "Value",
+        alias = "value",
+        alias = "clue_value",
+        default,
+        deserialize_with = "string_or_null"
+    )]
     pub value: String,
-    #[serde(rename = "Question")]
+    #[serde(rename = "Question", alias = "question", alias = "clue")]
     pub question: String,
-    #[serde(rename = "Answer")]
+    #[serde(rename = "Answer", alias = "answer", alias = "response")]
     pub answer: String,
 }
 
+impl Record {
+    /// The value in dollars, if the clue has one. Values look like "$1,200".
+    pub fn value(&self) -> Option<i32> {
+        self.value.trim().replace(['$', ','], "").parse().ok()
+    }
+
+    /// Daily Doubles aren't marked in the archive, but they're listed with the contestant's
+    /// wager, which rarely lands on one of the round's values.
+    pub fn is_daily_double(&self) -> bool {
+        match (self.round.parse::<Round>(), self.value()) {
+            (Ok(round @ (Round::Jeopardy | Round::DoubleJeopardy)), Some(value)) => {
+                !round.values(self.air_date).contains(&value)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Show numbers are quoted in the JSON export.
+fn number_or_string<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    use serde::de::Error;
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u32),
+        String(String),
+    }
+    match serde::Deserialize::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(s) => s.trim().parse().map_err(D::Error::custom),
+    }
+}
+
+/// Final Jeopardy! clues have a null value in the JSON export.
+fn string_or_null<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value: Option<String> = serde::Deserialize::deserialize(deserializer)?;
+    Ok(value.unwrap_or_default())
+}
+
+/// Reads the clues in an export of the J! Archive. The format is picked by extension: `.json`
+/// for a JSON array, `.tsv` for tab separated values and anything else as CSV.
+pub fn read_records<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => read_json(file),
+        Some("tsv") => read_delimited(file, b'\t'),
+        _ => read_delimited(file, b','),
+    }
+}
+
+fn read_json<R: std::io::Read>(reader: R) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+fn read_delimited<R: std::io::Read>(
+    reader: R,
+    delimiter: u8,
+) -> Result<Vec<Record>, Box<dyn std::error::Error>> {
+    let reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader);
+    Ok(reader
+        .into_deserialize()
+        .collect::<Result<Vec<Record>, _>>()?)
+}
+
+/// The formats clues can be written in by [`write_records`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The columns of `JEOPARDY.csv`, so exports can be seeded again.
+    Csv,
+    /// The same fields as the CSV, as an array of objects.
+    Json,
+    /// Notes for Anki's text importer, with the category and clue on the front and the answer
+    /// on the back.
+    Anki,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Csv, ExportFormat::Json, ExportFormat::Anki];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Anki => "anki",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Anki => "txt",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.as_str() == s)
+            .ok_or_else(|| format!("Unknown format: {}", s))
+    }
+}
+
+/// Writes `records` as `format`.
+pub fn write_records<W: std::io::Write>(
+    records: &[Record],
+    format: ExportFormat,
+    mut writer: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => serde_json::to_writer_pretty(writer, records)?,
+        ExportFormat::Anki => {
+            // tabs and newlines would split a note, and Anki renders fields as HTML anyway
+            let field = |text: &str| text.replace(['\t', '\n'], " ");
+            writeln!(writer, "#separator:tab\n#html:true\n#tags column:3")?;
+            for record in records {
+                writeln!(
+                    writer,
+                    "<b>{}</b> for {}<br>{}\t{}\tjeopardy show_{} {}",
+                    field(&record.category),
+                    field(&record.value),
+                    field(&record.question),
+                    field(&record.answer),
+                    record.show_number,
+                    field(&record.category).replace(' ', "_"),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The rounds of a show, as they're named in the `Round` column.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Round {
+    Jeopardy,
+    DoubleJeopardy,
+    FinalJeopardy,
+}
+
+impl Round {
+    pub const ALL: [Round; 3] = [Round::Jeopardy, Round::DoubleJeopardy, Round::FinalJeopardy];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Round::Jeopardy => "Jeopardy!",
+            Round::DoubleJeopardy => "Double Jeopardy!",
+            Round::FinalJeopardy => "Final Jeopardy!",
+        }
+    }
+
+    /// The number of categories on a full board for this round.
+    pub fn categories(&self) -> usize {
+        match self {
+            Round::Jeopardy | Round::DoubleJeopardy => 6,
+            Round::FinalJeopardy => 1,
+        }
+    }
+
+    /// The number of clues in each category for this round.
+    pub fn clues(&self) -> usize {
+        match self {
+            Round::Jeopardy | Round::DoubleJeopardy => 5,
+            Round::FinalJeopardy => 1,
+        }
+    }
+
+    /// The values on the board for this round in a show that aired on `air_date`. Clue values
+    /// were doubled on November 26, 2001.
+    pub fn values(&self, air_date: time::Date) -> &'static [i32] {
+        let doubled = (air_date.year(), air_date.month() as u8, air_date.day()) >= (2001, 11, 26);
+        match (self, doubled) {
+            (Round::Jeopardy, false) => &[100, 200, 300, 400, 500],
+            (Round::Jeopardy, true) | (Round::DoubleJeopardy, false) => &[200, 400, 600, 800, 1000],
+            (Round::DoubleJeopardy, true) => &[400, 800, 1200, 1600, 2000],
+            (Round::FinalJeopardy, _) => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for Round {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Round {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Round::ALL
+            .into_iter()
+            .find(|round| round.as_str() == s)
+            .ok_or_else(|| format!("Unknown round: {}", s))
+    }
+}
+
 pub struct Jeopardy {
     shows: Vec<u32>,
     by_show: std::collections::HashMap<u32, Vec<Record>>,
@@ -28,11 +255,14 @@ impl Jeopardy {
         let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
             .join("data")
             .join("JEOPARDY.csv");
+        Self::from_path(path)
+    }
 
-        let reader = csv::Reader::from_path(path)?;
-        let parsed = reader
-            .into_deserialize()
-            .collect::<Result<Vec<Record>, _>>()?;
+    /// Loads an export in any of the formats understood by [`read_records`].
+    pub fn from_path<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let parsed = read_records(path)?;
 
         let mut by_show = std::collections::HashMap::<u32, Vec<Record>>::new();
         for record in parsed {
@@ -74,6 +304,95 @@ impl Jeopardy {
         }
     }
 
+    /// All of the categories played in `round` of a random show, in the order they appear.
+    pub fn random_board(&self, round: Round) -> Result<Vec<Vec<&Record>>, &'static str> {
+        let rng = &mut thread_rng();
+        let shows = self
+            .shows
+            .iter()
+            .filter(|show_number| {
+                self.by_show[show_number]
+                    .iter()
+                    .any(|record| record.round == round.as_str())
+            })
+            .collect::<Vec<_>>();
+        match shows.choose(rng) {
+            None => Err("No shows with that round!"),
+            Some(show_number) => {
+                let mut board = Vec::<Vec<&Record>>::new();
+                for record in self.by_show[show_number].iter() {
+                    if record.round != round.as_str() {
+                        continue;
+                    }
+                    match board
+                        .iter_mut()
+                        .find(|category| category[0].category == record.category)
+                    {
+                        Some(category) => category.push(record),
+                        None => board.push(vec![record]),
+                    }
+                }
+                Ok(board)
+            }
+        }
+    }
+
+    /// A board for `round` assembled from complete categories across different shows.
+    pub fn random_mixed_board(&self, round: Round) -> Result<Vec<Vec<&Record>>, &'static str> {
+        let rng = &mut thread_rng();
+        let mut shows = self.shows.iter().collect::<Vec<_>>();
+        shows.shuffle(rng);
+        let board = shows
+            .into_iter()
+            .filter_map(|show_number| {
+                self.show_categories(show_number)
+                    .into_iter()
+                    .filter(|category| {
+                        category[0].round == round.as_str() && category.len() == round.clues()
+                    })
+                    .choose(rng)
+            })
+            .take(round.categories())
+            .collect::<Vec<_>>();
+        if board.is_empty() {
+            Err("No shows with that round!")
+        } else {
+            Ok(board)
+        }
+    }
+
+    /// A random category that `predicate` accepts. Shows are searched in a random order and
+    /// the category is chosen from the first show with any that match.
+    pub fn random_matching<F>(&self, predicate: F) -> Option<Vec<&Record>>
+    where
+        F: Fn(&[&Record]) -> bool,
+    {
+        let rng = &mut thread_rng();
+        let mut shows = self.shows.iter().collect::<Vec<_>>();
+        shows.shuffle(rng);
+        shows.into_iter().find_map(|show_number| {
+            self.show_categories(show_number)
+                .into_iter()
+                .filter(|category| predicate(category))
+                .choose(rng)
+        })
+    }
+
+    /// Every category in a show, across all of its rounds, in the order they appear.
+    fn show_categories(&self, show_number: &u32) -> Vec<Vec<&Record>> {
+        let mut categories = Vec::<Vec<&Record>>::new();
+        for record in self.by_show[show_number].iter() {
+            let category = categories.iter_mut().find(|category| {
+                category[0].round == record.round && category[0].category == record.category
+            });
+            match category {
+                Some(category) => category.push(record),
+                None => categories.push(vec![record]),
+            }
+        }
+        categories
+    }
+
     pub fn fmt_category(category: &Vec<&Record>) -> String {
         use std::fmt::Write;
         let mut out = String::new();
@@ -109,4 +428,139 @@ mod tests {
         let category = collection.random().unwrap();
         let _s = Jeopardy::fmt_category(&category);
     }
+
+    #[test]
+    fn board_works() {
+        let collection = Jeopardy::new().unwrap();
+        for round in Round::ALL {
+            let board = collection.random_board(round).unwrap();
+            assert!(!board.is_empty());
+            assert!(board.len() <= round.categories());
+            assert!(board
+                .iter()
+                .flatten()
+                .all(|record| record.round == round.as_str()));
+        }
+    }
+
+    #[test]
+    fn mixed_board_works() {
+        let collection = Jeopardy::new().unwrap();
+        for round in Round::ALL {
+            let board = collection.random_mixed_board(round).unwrap();
+            assert!(!board.is_empty());
+            assert!(board.len() <= round.categories());
+            assert!(board.iter().all(|category| category.len() == round.clues()));
+            let mut shows = board
+                .iter()
+                .map(|category| category[0].show_number)
+                .collect::<Vec<_>>();
+            shows.sort_unstable();
+            shows.dedup();
+            assert_eq!(shows.len(), board.len());
+        }
+    }
+
+    #[test]
+    fn random_matching_works() {
+        let collection = Jeopardy::new().unwrap();
+        let category = collection
+            .random_matching(|category| category[0].round == Round::Jeopardy.as_str())
+            .unwrap();
+        assert!(category
+            .iter()
+            .all(|record| record.round == Round::Jeopardy.as_str()
+                && record.category == category[0].category));
+        assert!(collection.random_matching(|_| false).is_none());
+    }
+
+    #[test]
+    fn reads_json_export() {
+        let json = r#"[
+            {"category": "HISTORY", "air_date": "2004-12-31", "question": "'Galileo was under house arrest for espousing this man's theory'", "value": "$200", "answer": "Copernicus", "round": "Jeopardy!", "show_number": "4680"},
+            {"category": "THE SOLAR SYSTEM", "air_date": "2004-12-31", "question": "'The only planet named for a Greek god'", "value": null, "answer": "Uranus", "round": "Final Jeopardy!", "show_number": "4680"}
+        ]"#;
+        let records = read_json(json.as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].show_number, 4680);
+        assert_eq!(records[0].value, "$200");
+        assert_eq!(records[1].value, "");
+        assert_eq!(records[1].round, Round::FinalJeopardy.as_str());
+    }
+
+    #[test]
+    fn reads_tsv_export() {
+        let tsv = "show_number\tair_date\tround\tcategory\tclue_value\tclue\tresponse\n\
+                   8000\t2019-03-01\tDouble Jeopardy!\tPOTENT POTABLES\t$800\tA pirate's favorite\trum\n";
+        let records = read_delimited(tsv.as_bytes(), b'\t').unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].show_number, 8000);
+        assert_eq!(records[0].category, "POTENT POTABLES");
+        assert_eq!(records[0].question, "A pirate's favorite");
+        assert_eq!(records[0].answer, "rum");
+    }
+
+    #[test]
+    fn daily_doubles() {
+        let tsv = "show_number\tair_date\tround\tcategory\tvalue\tquestion\tanswer\n\
+                   1000\t1989-01-02\tJeopardy!\tA\t$500\tq\ta\n\
+                   1000\t1989-01-02\tJeopardy!\tA\t$1,000\tq\ta\n\
+                   5000\t2006-05-02\tJeopardy!\tA\t$1,000\tq\ta\n\
+                   5000\t2006-05-02\tDouble Jeopardy!\tA\t$2,127\tq\ta\n\
+                   5000\t2006-05-02\tFinal Jeopardy!\tA\tNone\tq\ta\n";
+        let records = read_delimited(tsv.as_bytes(), b'\t').unwrap();
+        let daily_doubles = records
+            .iter()
+            .map(Record::is_daily_double)
+            .collect::<Vec<_>>();
+        assert_eq!(daily_doubles, [false, true, false, true, false]);
+        assert_eq!(records[3].value(), Some(2127));
+        assert_eq!(records[4].value(), None);
+    }
+
+    #[test]
+    fn exports_read_back() {
+        let tsv = "show_number\tair_date\tround\tcategory\tvalue\tquestion\tanswer\n\
+                   4680\t2004-12-31\tJeopardy!\tHISTORY\t$200\tA \"quoted\", clue\tCopernicus\n\
+                   4680\t2004-12-31\tFinal Jeopardy!\tTHE SOLAR SYSTEM\tNone\tOne\ttwo\n";
+        let records = read_delimited(tsv.as_bytes(), b'\t').unwrap();
+
+        let mut csv = vec![];
+        write_records(&records, ExportFormat::Csv, &mut csv).unwrap();
+        assert!(csv.starts_with(b"Show Number,Air Date,Round,Category,Value,Question,Answer\n"));
+        let mut json = vec![];
+        write_records(&records, ExportFormat::Json, &mut json).unwrap();
+        for read in [
+            read_delimited(csv.as_slice(), b',').unwrap(),
+            read_json(json.as_slice()).unwrap(),
+        ] {
+            assert_eq!(read.len(), records.len());
+            for (read, record) in read.iter().zip(records.iter()) {
+                assert_eq!(read.air_date, record.air_date);
+                assert_eq!(read.value, record.value);
+                assert_eq!(read.question, record.question);
+            }
+        }
+
+        let mut anki = vec![];
+        write_records(&records, ExportFormat::Anki, &mut anki).unwrap();
+        let anki = String::from_utf8(anki).unwrap();
+        let notes = anki
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect::<Vec<_>>();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(
+            notes[0],
+            "<b>HISTORY</b> for $200<br>A \"quoted\", clue\tCopernicus\tjeopardy show_4680 HISTORY"
+        );
+    }
+
+    #[test]
+    fn round_names() {
+        for round in Round::ALL {
+            assert_eq!(round.as_str().parse::<Round>(), Ok(round));
+        }
+        assert!("Tiebreaker".parse::<Round>().is_err());
+    }
 }
@@ -1,5 +1,18 @@
+/// `None` when the bot was started without a `DATABASE_URL`.
 pub struct DB;
 
 impl serenity::prelude::TypeMapKey for DB {
-    type Value = db::DB;
+    type Value = Option<db::DB>;
+}
+
+/// Where Jeopardy! clues come from: the database, or the bundled archive without one.
+pub struct Source;
+
+impl serenity::prelude::TypeMapKey for Source {
+    type Value = std::sync::Arc<dyn db::source::JeopardySource>;
+}
+
+/// For features that keep state, which only works with a database.
+pub fn required(db: Option<db::DB>) -> eyre::Result<db::DB> {
+    db.ok_or_else(|| eyre::eyre!("That needs a database, and there isn't one configured."))
 }
@@ -0,0 +1,809 @@
+//! The `/htw` command and the buttons on its messages. The game itself is in [`crate::wumpus`].
+
+use crate::wumpus::{self, HuntTheWumpus};
+use serenity::builder::{CreateComponents, CreateEmbed, CreateInteractionResponse};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::application::interaction::{
+    application_command::{ApplicationCommandInteraction, CommandDataOption},
+    message_component::MessageComponentInteraction,
+    InteractionResponseType,
+};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+mod map;
+
+/// Games in progress, one per player and one per channel. They're kept in the database too, so
+/// that they survive restarts, and are forgotten after `EXPIRY_DAYS`. Each game has a lock of
+/// its own, so that loading or saving one doesn't hold up the others.
+pub struct Games;
+
+impl TypeMapKey for Games {
+    type Value = std::sync::Arc<tokio::sync::Mutex<GameMap>>;
+}
+
+type GameMap = std::collections::HashMap<Key, std::sync::Arc<tokio::sync::Mutex<Slot>>>;
+
+/// Where a key's game goes once it has been looked for in the database, with when it was last
+/// played.
+pub struct Slot {
+    loaded: bool,
+    game: Option<HuntTheWumpus>,
+    played_at: std::time::Instant,
+}
+
+const EXPIRY_DAYS: i32 = 7;
+
+const MAP_FILE: &str = "htw-map.png";
+
+/// Whose game it is: a player's own, or the one shared by everyone in a channel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    Player(UserId),
+    Channel(ChannelId),
+}
+
+impl Key {
+    /// The id the game is saved under. Users and channels never share ids.
+    fn id(self) -> u64 {
+        match self {
+            Key::Player(user_id) => user_id.0,
+            Key::Channel(channel_id) => channel_id.0,
+        }
+    }
+
+    fn is_shared(self) -> bool {
+        matches!(self, Key::Channel(_))
+    }
+
+    fn no_game(self) -> &'static str {
+        match self {
+            Key::Player(_) => "There's no game running for you.",
+            Key::Channel(_) => "There's no game running in this channel.",
+        }
+    }
+}
+
+/// Buttons carry what they do and the room they do it to, e.g. `htw:move:7`. Buttons under a
+/// shared game say so first, e.g. `htw:channel:move:7`.
+const CHANNEL: &str = "channel";
+const MOVE: &str = "move";
+const SHOOT: &str = "shoot";
+/// Switches the room buttons between moving and shooting.
+const AIM: &str = "aim";
+const WALK: &str = "walk";
+
+/// What the room buttons under a game do.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Move,
+    Shoot,
+}
+
+/// The buttons under a game: one for each tunnel out of the player's room, which in dark caves
+/// don't say where they lead.
+struct Buttons {
+    tunnels: Vec<usize>,
+    mode: Mode,
+    dark: bool,
+    shared: bool,
+}
+
+/// A game as it's shown after a turn, with a note about anything that went wrong. Finished games
+/// are shown with a map of the whole cave, from where the player who finished it stood.
+struct Reply {
+    content: String,
+    embed: Option<CreateEmbed>,
+    buttons: Option<Buttons>,
+    map: Option<Vec<u8>>,
+    ephemeral: bool,
+}
+
+impl Reply {
+    fn game(game: &HuntTheWumpus, mode: Mode, key: Key, user_id: UserId) -> Self {
+        let mut embed = CreateEmbed::default();
+        embed.title("Hunt the Wumpus").description(game.status());
+        if game.is_running() {
+            embed.footer(|footer| {
+                footer.text(format!(
+                    "Arrows: {} · {}{}, {} rooms",
+                    game.arrows(),
+                    if game.is_dark() { "Dark " } else { "" },
+                    game.layout().name(),
+                    game.room_count()
+                ))
+            });
+        }
+        let reply = Self {
+            content: String::new(),
+            embed: Some(embed),
+            buttons: game.is_running().then(|| Buttons {
+                tunnels: game.tunnels().to_vec(),
+                mode,
+                dark: game.is_dark(),
+                shared: key.is_shared(),
+            }),
+            map: None,
+            ephemeral: false,
+        };
+        match game.is_running() {
+            true => reply,
+            false => reply.with_map(game, user_id.0),
+        }
+    }
+
+    fn text(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            embed: None,
+            buttons: None,
+            map: None,
+            ephemeral: false,
+        }
+    }
+
+    fn private(content: impl Into<String>) -> Self {
+        Self {
+            ephemeral: true,
+            ..Self::text(content)
+        }
+    }
+
+    fn note(mut self, note: impl Into<String>) -> Self {
+        self.content = note.into();
+        self
+    }
+
+    /// Adds a map of what `player` knows about the cave.
+    fn with_map(mut self, game: &HuntTheWumpus, player: u64) -> Self {
+        match map::render(&game.map(player)) {
+            Ok(png) => self.map = Some(png),
+            Err(err) => eprintln!("Couldn't draw the Hunt the Wumpus map: {}", err),
+        }
+        self
+    }
+
+    fn respond<'a, 'b>(
+        self,
+        response: &'b mut CreateInteractionResponse<'a>,
+        kind: InteractionResponseType,
+    ) -> &'b mut CreateInteractionResponse<'a> {
+        let Self {
+            content,
+            mut embed,
+            buttons: room_buttons,
+            map,
+            ephemeral,
+        } = self;
+        if let (Some(embed), Some(_)) = (&mut embed, &map) {
+            embed
+                .image(format!("attachment://{}", MAP_FILE))
+                .field("Map", map::LEGEND, false);
+        }
+        response.kind(kind).interaction_response_data(|message| {
+            if let Some(map) = map {
+                message.add_file(AttachmentType::Bytes {
+                    data: map.into(),
+                    filename: MAP_FILE.to_owned(),
+                });
+            }
+            message
+                .content(content)
+                .set_embeds(embed)
+                .components(|components| match &room_buttons {
+                    Some(room_buttons) => buttons(components, room_buttons),
+                    None => components,
+                })
+                .ephemeral(ephemeral)
+        })
+    }
+}
+
+fn buttons<'a>(
+    components: &'a mut CreateComponents,
+    Buttons {
+        tunnels,
+        mode,
+        dark,
+        shared,
+    }: &Buttons,
+) -> &'a mut CreateComponents {
+    components.create_action_row(|row| {
+        for (i, room) in tunnels.iter().enumerate() {
+            let label = match dark {
+                true => format!("Tunnel {}", i + 1),
+                false => format!("Room {}", room),
+            };
+            row.create_button(|button| match mode {
+                Mode::Move => button
+                    .custom_id(custom_id(*shared, MOVE, Some(*room)))
+                    .label(label)
+                    .style(ButtonStyle::Primary),
+                Mode::Shoot => button
+                    .custom_id(custom_id(*shared, SHOOT, Some(*room)))
+                    .label(format!("Shoot {}", label.to_lowercase()))
+                    .style(ButtonStyle::Danger),
+            });
+        }
+        row.create_button(|button| match mode {
+            Mode::Move => button
+                .custom_id(custom_id(*shared, AIM, None))
+                .label("Shoot...")
+                .style(ButtonStyle::Secondary),
+            Mode::Shoot => button
+                .custom_id(custom_id(*shared, WALK, None))
+                .label("Cancel")
+                .style(ButtonStyle::Secondary),
+        })
+    })
+}
+
+fn custom_id(shared: bool, action: &str, room: Option<usize>) -> String {
+    let prefix = match shared {
+        true => format!("{}:{}", crate::WUMPUS_CMD, CHANNEL),
+        false => crate::WUMPUS_CMD.to_owned(),
+    };
+    match room {
+        Some(room) => format!("{}:{}:{}", prefix, action, room),
+        None => format!("{}:{}", prefix, action),
+    }
+}
+
+fn parse_custom_id(custom_id: &str) -> (bool, &str, Option<usize>) {
+    let mut parts = custom_id.split(':').skip(1).peekable();
+    let shared = parts.next_if_eq(&CHANNEL).is_some();
+    let action = parts.next().unwrap_or_default();
+    let room = parts.next().and_then(|room| room.parse().ok());
+    (shared, action, room)
+}
+
+/// Handles `/htw`. Players in a channel's shared game play it, and everyone else plays their
+/// own.
+pub async fn command(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    match command.data.options[0].name.as_str() {
+        "stats" => return stats(ctx, &command).await,
+        "leaderboard" => return leaderboard(ctx, &command).await,
+        _ => {}
+    }
+
+    let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let user_id = command.user.id;
+    let channel = Key::Channel(command.channel_id);
+
+    let cmd = &command.data.options[0];
+    let option = |name: &str| {
+        cmd.options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_ref())
+    };
+    // starting a game of your own is the only command that never involves the channel's game
+    let key = match cmd.name.as_str() {
+        "start" if option("together").and_then(|value| value.as_bool()) == Some(true) => channel,
+        "start" => Key::Player(user_id),
+        _ => channel,
+    };
+    let mut slot = lock(&games, db.clone(), key).await;
+    let key = match cmd.name.as_str() {
+        "start" | "join" | "leave" => key,
+        _ if slot
+            .game
+            .as_ref()
+            .is_some_and(|game| game.has_player(user_id.0)) =>
+        {
+            key
+        }
+        _ => {
+            drop(slot);
+            slot = lock(&games, db.clone(), Key::Player(user_id)).await;
+            Key::Player(user_id)
+        }
+    };
+    let slot = &mut *slot;
+
+    let reply = match (cmd.name.as_str(), slot.game.as_mut()) {
+        ("start", Some(game)) => Reply::game(game, Mode::Move, key, user_id).note(match key {
+            Key::Player(_) => "There is already a game running for you.",
+            Key::Channel(_) => {
+                "There is already a game running in this channel. Join it with /htw join."
+            }
+        }),
+        ("start", None) => {
+            let choice = |name: &str| option(name).and_then(|value| value.as_str());
+            let defaults = wumpus::Settings::default();
+            let settings = wumpus::Settings {
+                rules: choice("rules")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(defaults.rules),
+                layout: choice("cave")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(defaults.layout),
+                rooms: option("rooms")
+                    .and_then(|value| value.as_u64())
+                    .map_or(defaults.rooms, |rooms| rooms as usize),
+                dark: option("dark")
+                    .and_then(|value| value.as_bool())
+                    .unwrap_or(defaults.dark),
+                mode: choice("mode")
+                    .and_then(|value| value.parse().ok())
+                    .unwrap_or(defaults.mode),
+            };
+            match HuntTheWumpus::new(settings, &[user_id.0]) {
+                Ok(game) => {
+                    slot.played_at = std::time::Instant::now();
+                    Reply::game(slot.game.insert(game), Mode::Move, key, user_id)
+                }
+                Err(err) => Reply::private(err.to_string()),
+            }
+        }
+        ("join", None) => Reply::private(
+            "There's no game running in this channel. Start one with /htw start together:True.",
+        ),
+        ("join", Some(game)) => match game.join(user_id.0) {
+            Ok(()) => {
+                slot.played_at = std::time::Instant::now();
+                Reply::game(game, Mode::Move, key, user_id)
+                    .note(format!("{} joined the hunt!", user_id.mention()))
+            }
+            Err(err) => Reply::private(err.to_string()),
+        },
+        ("leave", Some(game)) => match game.leave(user_id.0) {
+            None => Reply::private("You're not in this hunt."),
+            Some(outcome) => {
+                let mut outcomes = vec![outcome];
+                let reply = if game.player_count() == 0 {
+                    slot.game = None;
+                    Reply::text(format!(
+                        "{} left the hunt, and nobody's left to play.",
+                        user_id.mention()
+                    ))
+                } else {
+                    let note = format!("{} left the hunt.", user_id.mention());
+                    let reply = Reply::game(game, Mode::Move, key, user_id).note(note);
+                    if !game.is_running() {
+                        outcomes.extend(game.outcomes());
+                        slot.game = None;
+                    }
+                    reply
+                };
+                record(db.clone(), key, command.guild_id, outcomes);
+                reply
+            }
+        },
+        (_, None) => Reply::text(key.no_game()),
+        (name, Some(game)) => {
+            slot.played_at = std::time::Instant::now();
+            let wait = not_your_turn(key, game, user_id);
+            let reply = match (name, wait) {
+                ("status", _) => Reply::game(game, Mode::Move, key, user_id),
+                ("map", _) => Reply::game(game, Mode::Move, key, user_id).with_map(game, user_id.0),
+                ("move" | "shoot", Some(wait)) => Reply::private(wait),
+                ("move", None) => {
+                    let choice = cmd.options[0].value.as_ref().unwrap().as_u64().unwrap() as usize;
+                    let new_room = match game.is_dark() {
+                        true => game.follow_tunnels(&[choice]).ok().map(|path| path[0]),
+                        false => Some(choice),
+                    };
+                    if new_room.is_some_and(|new_room| game.move_player_to(new_room)) {
+                        Reply::game(game, Mode::Move, key, user_id)
+                    } else {
+                        Reply::game(game, Mode::Move, key, user_id).note(match game.is_dark() {
+                            true => "That's not a tunnel you can take!",
+                            false => "That's not a room you can move to!",
+                        })
+                    }
+                }
+                ("shoot", None) => match shoot(game, cmd) {
+                    Ok(()) => Reply::game(game, Mode::Move, key, user_id),
+                    Err(note) => Reply::game(game, Mode::Move, key, user_id).note(note),
+                },
+                _ => Reply::text("Unrecognized subcommand"),
+            };
+            if !game.is_running() {
+                record(db.clone(), key, command.guild_id, game.outcomes());
+                slot.game = None;
+            }
+            reply
+        }
+    };
+
+    // looking at a game doesn't change it, so only turns need saving
+    if !matches!(cmd.name.as_str(), "status" | "map") {
+        persist(slot, db, key);
+    }
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            reply.respond(response, InteractionResponseType::ChannelMessageWithSource)
+        })
+        .await?;
+    Ok(())
+}
+
+/// Handles `/htw stats`: how a player's hunts have ended, wherever they played them.
+async fn stats(ctx: &Context, command: &ApplicationCommandInteraction) -> eyre::Result<()> {
+    use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+
+    let user = command.data.options[0]
+        .options
+        .iter()
+        .find(|option| option.name == "user")
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| command.user.clone());
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        let user_id = user.id.0;
+        move || crate::db_support::required(db)?.wumpus_stats(user_id)
+    });
+
+    let reply = match result {
+        Ok(outcomes) if outcomes.is_empty() => {
+            Reply::private(format!("{} hasn't finished a hunt yet.", user.name))
+        }
+        Ok(outcomes) => {
+            let games = outcomes.iter().map(|outcome| outcome.games).sum::<i64>();
+            let moves = outcomes.iter().map(|outcome| outcome.moves).sum::<i64>();
+            let arrows = outcomes
+                .iter()
+                .map(|outcome| outcome.arrows_used)
+                .sum::<i64>();
+            let won = outcomes.iter().find(|outcome| outcome.outcome == "Won");
+            let wins = won.map_or(0, |won| won.games);
+            let endings = outcomes
+                .iter()
+                .map(|outcome| format!("{}: {}", ending(&outcome.outcome), outcome.games))
+                .collect::<Vec<_>>();
+
+            let mut embed = CreateEmbed::default();
+            embed
+                .title(format!("{}'s Hunt the Wumpus stats", user.name))
+                .thumbnail(user.face())
+                .field("Hunts", games, true)
+                .field("Wins", wins, true)
+                .field(
+                    "Win rate",
+                    format!("{:.0}%", wins as f64 * 100.0 / games as f64),
+                    true,
+                )
+                .field(
+                    "Fewest moves to win",
+                    won.map_or("-".to_owned(), |won| won.fewest_moves.to_string()),
+                    true,
+                )
+                .field("Moves", moves, true)
+                .field("Arrows shot", arrows, true)
+                .field("Endings", endings.join("\n"), false);
+            Reply {
+                embed: Some(embed),
+                ..Reply::text("")
+            }
+        }
+        Err(err) => Reply::private(err.to_string()),
+    };
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            reply.respond(response, InteractionResponseType::ChannelMessageWithSource)
+        })
+        .await?;
+    Ok(())
+}
+
+/// Handles `/htw leaderboard`: who has killed the Wumpus most often in this server.
+async fn leaderboard(ctx: &Context, command: &ApplicationCommandInteraction) -> eyre::Result<()> {
+    let result = match command.guild_id {
+        Some(guild_id) => tokio::task::block_in_place({
+            let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+            move || crate::db_support::required(db)?.wumpus_leaderboard(guild_id.0, 10)
+        }),
+        None => Err(eyre::eyre!("Leaderboards are only kept in servers.")),
+    };
+
+    let reply = match result {
+        Ok(leaders) => {
+            let mut embed = CreateEmbed::default();
+            embed.title("Hunt the Wumpus Leaderboard");
+            if leaders.is_empty() {
+                embed.description("Nobody has finished a hunt yet!");
+            } else {
+                let lines = leaders
+                    .iter()
+                    .enumerate()
+                    .map(|(index, leader)| {
+                        format!(
+                            "{}. {} - {} {} in {} {}{}",
+                            index + 1,
+                            UserId(leader.discord_user_id as u64).mention(),
+                            leader.wins,
+                            if leader.wins == 1 { "win" } else { "wins" },
+                            leader.games,
+                            if leader.games == 1 { "hunt" } else { "hunts" },
+                            leader
+                                .fewest_moves
+                                .map(|moves| format!(" (best: {} moves)", moves))
+                                .unwrap_or_default()
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                embed.description(lines.join("\n"));
+            }
+            Reply {
+                embed: Some(embed),
+                ..Reply::text("")
+            }
+        }
+        Err(err) => Reply::private(err.to_string()),
+    };
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            reply.respond(response, InteractionResponseType::ChannelMessageWithSource)
+        })
+        .await?;
+    Ok(())
+}
+
+/// How a game ended, as `/htw stats` lists it.
+fn ending(outcome: &str) -> &str {
+    match outcome {
+        "Won" => "Killed the Wumpus",
+        "Lost" => "Beaten to the Wumpus",
+        "GameOverShot" => "Shot by an arrow",
+        "GameOverWumpus" => "Eaten by the Wumpus",
+        "GameOverPit" => "Fell into a pit",
+        "GameOverArrows" => "Ran out of arrows",
+        "Abandoned" => "Left the hunt",
+        other => other,
+    }
+}
+
+/// Handles the buttons under a game. Only the player whose game it is can press them, and in a
+/// shared game only the player whose turn it is.
+pub async fn component(ctx: &Context, component: MessageComponentInteraction) -> eyre::Result<()> {
+    let user_id = component.user.id;
+    let (shared, action, room) = parse_custom_id(&component.data.custom_id);
+    let owner = component
+        .message
+        .interaction
+        .as_ref()
+        .map(|interaction| interaction.user.id);
+    if !shared && owner != Some(user_id) {
+        component
+            .create_interaction_response(&ctx.http, |response| {
+                Reply::private("This isn't your game. Start your own with /htw start.")
+                    .respond(response, InteractionResponseType::ChannelMessageWithSource)
+            })
+            .await?;
+        return Ok(());
+    }
+    let key = match shared {
+        true => Key::Channel(component.channel_id),
+        false => Key::Player(user_id),
+    };
+
+    let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let mut slot = lock(&games, db.clone(), key).await;
+    let slot = &mut *slot;
+
+    let mut kind = InteractionResponseType::UpdateMessage;
+    let reply = match slot.game.as_mut() {
+        None => Reply::text(key.no_game()),
+        Some(game) => match not_your_turn(key, game, user_id) {
+            Some(wait) => {
+                kind = InteractionResponseType::ChannelMessageWithSource;
+                Reply::private(wait)
+            }
+            None => {
+                slot.played_at = std::time::Instant::now();
+                let reply = match (action, room) {
+                    (MOVE, Some(room)) => {
+                        if game.move_player_to(room) {
+                            Reply::game(game, Mode::Move, key, user_id)
+                        } else {
+                            Reply::game(game, Mode::Move, key, user_id)
+                                .note("That's not a room you can move to!")
+                        }
+                    }
+                    // buttons only shoot into the next room; `/htw shoot` can send arrows farther
+                    (SHOOT, Some(room)) => {
+                        let shot = match game.rules() {
+                            wumpus::Rules::Classic => game.shoot_path(&[room]).is_ok(),
+                            wumpus::Rules::Current => game.shoot(room, 1),
+                        };
+                        if shot {
+                            Reply::game(game, Mode::Move, key, user_id)
+                        } else {
+                            Reply::game(game, Mode::Shoot, key, user_id)
+                                .note("That's not a room you can shoot into!")
+                        }
+                    }
+                    (AIM, _) => Reply::game(game, Mode::Shoot, key, user_id),
+                    _ => Reply::game(game, Mode::Move, key, user_id),
+                };
+                if !game.is_running() {
+                    record(db.clone(), key, component.guild_id, game.outcomes());
+                    slot.game = None;
+                }
+                reply
+            }
+        },
+    };
+
+    if kind == InteractionResponseType::UpdateMessage && matches!(action, MOVE | SHOOT) {
+        persist(slot, db, key);
+    }
+
+    component
+        .create_interaction_response(&ctx.http, |response| reply.respond(response, kind))
+        .await?;
+    Ok(())
+}
+
+/// Why `user_id` can't take a turn in `game` right now, if they can't. Only shared games have
+/// turns.
+fn not_your_turn(key: Key, game: &HuntTheWumpus, user_id: UserId) -> Option<String> {
+    if !key.is_shared() {
+        None
+    } else if !game.is_hunting(user_id.0) {
+        Some("You're not hunting in this game. Join it with /htw join.".to_owned())
+    } else if game.current_player() != user_id.0 {
+        Some(format!("It's <@{}>'s turn.", game.current_player()))
+    } else {
+        None
+    }
+}
+
+/// Shoots an arrow into `at_room`. Under the current rules it travels `room_travel` rooms from
+/// there, and under the classic rules it follows `path`.
+fn shoot(game: &mut HuntTheWumpus, cmd: &CommandDataOption) -> Result<(), String> {
+    let option = |name: &str| {
+        cmd.options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_ref())
+    };
+    let at_room = option("at_room").and_then(|value| value.as_u64()).unwrap() as usize;
+    let room_travel = option("room_travel").and_then(|value| value.as_u64());
+    // dark caves are shot through by tunnel number instead of by room
+    let dark = game.is_dark();
+
+    match game.rules() {
+        wumpus::Rules::Classic => {
+            let rest = option("path")
+                .and_then(|value| value.as_str())
+                .unwrap_or_default();
+            let rest = rest
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|room| !room.is_empty())
+                .map(|room| room.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| match dark {
+                    true => "A path is a list of tunnel numbers, like 2 3.".to_owned(),
+                    false => "A path is a list of room numbers, like 7 12.".to_owned(),
+                })?;
+            let mut path = std::iter::once(at_room).chain(rest).collect::<Vec<_>>();
+            if dark {
+                path = game.follow_tunnels(&path).map_err(|err| err.to_string())?;
+            }
+            game.shoot_path(&path).map_err(|err| err.to_string())
+        }
+        wumpus::Rules::Current => {
+            let room_travel =
+                room_travel.ok_or_else(|| "How many rooms should the arrow travel?".to_owned())?;
+            let at_room = match dark {
+                true => game
+                    .follow_tunnels(&[at_room])
+                    .map_err(|err| err.to_string())?[0],
+                false => at_room,
+            };
+            match game.shoot(at_room, room_travel as usize) {
+                true => Ok(()),
+                false => Err("That's not a room you can shoot into!".to_owned()),
+            }
+        }
+    }
+}
+
+/// Forgets games nobody has played for a while, then locks `key`'s game, looking for it in the
+/// database the first time. Only the one game stays locked while it loads.
+async fn lock(
+    games: &tokio::sync::Mutex<GameMap>,
+    db: Option<db::DB>,
+    key: Key,
+) -> tokio::sync::OwnedMutexGuard<Slot> {
+    let expiry = std::time::Duration::from_secs(EXPIRY_DAYS as u64 * 24 * 60 * 60);
+    let slot = {
+        let mut games = games.lock().await;
+        // games being played right now are locked, and certainly not expired
+        games.retain(|_, slot| {
+            slot.try_lock()
+                .map_or(true, |slot| slot.played_at.elapsed() < expiry)
+        });
+        games
+            .entry(key)
+            .or_insert_with(|| {
+                std::sync::Arc::new(tokio::sync::Mutex::new(Slot {
+                    loaded: false,
+                    game: None,
+                    played_at: std::time::Instant::now(),
+                }))
+            })
+            .clone()
+    };
+    let mut slot = slot.lock_owned().await;
+    // a game started while the database couldn't be reached is newer than anything saved
+    if slot.loaded || slot.game.is_some() {
+        return slot;
+    }
+    let mut db = match db {
+        Some(db) => db,
+        None => return slot,
+    };
+    let result = tokio::task::block_in_place(move || -> eyre::Result<_> {
+        match db.wumpus_game(key.id(), EXPIRY_DAYS)? {
+            Some(state) => Ok(Some(serde_json::from_value(state)?)),
+            None => Ok(None),
+        }
+    });
+    match result {
+        Ok(game) => {
+            slot.loaded = true;
+            slot.game = game;
+        }
+        Err(err) => eprintln!(
+            "Couldn't load the Hunt the Wumpus game for {:?}: {}",
+            key, err
+        ),
+    }
+    slot
+}
+
+/// Records how a game went for the players in `outcomes`, whether it's over or they left it.
+fn record(db: Option<db::DB>, key: Key, guild_id: Option<GuildId>, outcomes: Vec<wumpus::Outcome>) {
+    let mut db = match db {
+        Some(db) => db,
+        None => return,
+    };
+    let results = outcomes
+        .into_iter()
+        .map(|outcome| db::models::NewWumpusResult {
+            id: uuid::Uuid::new_v4(),
+            // games saved before there were shared caves don't know whose they are
+            discord_user_id: match key {
+                Key::Player(user_id) => user_id.0,
+                Key::Channel(_) => outcome.player,
+            } as i64,
+            discord_guild_id: guild_id.map(|guild_id| guild_id.0 as i64),
+            outcome: outcome.result,
+            moves: outcome.moves as i32,
+            arrows_used: outcome.arrows_used as i32,
+        })
+        .collect::<Vec<_>>();
+    if let Err(err) = tokio::task::block_in_place(move || db.record_wumpus_results(&results)) {
+        eprintln!(
+            "Couldn't record the Hunt the Wumpus results for {:?}: {}",
+            key, err
+        );
+    }
+}
+
+/// Saves `key`'s game, or deletes it once it's over.
+fn persist(slot: &Slot, db: Option<db::DB>, key: Key) {
+    let (mut db, game) = match db {
+        Some(db) => (db, slot.game.as_ref()),
+        None => return,
+    };
+    let result = tokio::task::block_in_place(move || match game {
+        Some(game) => db.save_wumpus_game(key.id(), &serde_json::to_value(game)?),
+        None => db.delete_wumpus_game(key.id()),
+    });
+    if let Err(err) = result {
+        eprintln!(
+            "Couldn't save the Hunt the Wumpus game for {:?}: {}",
+            key, err
+        );
+    }
+}
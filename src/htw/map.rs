@@ -0,0 +1,310 @@
+//! Maps of Hunt the Wumpus caves, drawn as PNGs like the images of `/rayz`.
+
+use crate::wumpus::{Layout, Map, TORUS_ROWS};
+
+const SIZE: usize = 512;
+const MAX_ROOM_RADIUS: f32 = 18.;
+
+/// Room numbers are drawn in a font three pixels wide and five tall.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+type Color = [u8; 3];
+
+const BACKGROUND: Color = [24, 24, 32];
+const TUNNEL: Color = [90, 90, 104];
+const UNVISITED: Color = [56, 56, 68];
+const VISITED: Color = [160, 160, 172];
+const CURRENT: Color = [240, 200, 60];
+const HUNTER: Color = [80, 200, 120];
+const WUMPUS: Color = [210, 50, 50];
+const BATS: Color = [150, 80, 190];
+const PIT: Color = [70, 130, 220];
+const DARK_TEXT: Color = [16, 16, 16];
+const LIGHT_TEXT: Color = [232, 232, 232];
+
+/// What the colors on a map mean.
+pub const LEGEND: &str = "🟨 You · 🟩 Other hunters · ⬜ Visited\n\
+    Dots are what you noticed there: 🟥 Wumpus · 🟪 Bats · 🟦 Draft\n\
+    Once the hunt is over, rooms show the 🟥 Wumpus, 🟪 Bats and 🟦 Pits.";
+
+/// Draws the cave as far as the player knows it. In dark caves only the rooms they've been in
+/// are drawn, until the game is over and the whole cave is shown.
+pub fn render(map: &Map) -> Result<Vec<u8>, png::EncodingError> {
+    let positions = positions(map);
+    let radius = positions
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| positions[..i].iter().map(move |b| distance(*a, *b)))
+        .fold(MAX_ROOM_RADIUS / 0.4, f32::min)
+        * 0.4;
+    let known = |room: usize| {
+        map.hazards.is_some() || !map.dark || room == map.room || map.seen.contains_key(&room)
+    };
+
+    let mut canvas = Canvas::new();
+    for room in 0..map.cave.len() {
+        for &to in map.cave.tunnels(room) {
+            if room < to && known(room) && known(to) {
+                let wraps = map.layout == Layout::Torus;
+                canvas.tunnel(positions[room], positions[to], wraps);
+            }
+        }
+    }
+
+    for (room, &center) in positions.iter().enumerate() {
+        if !known(room) {
+            continue;
+        }
+        let hazard = map.hazards.and_then(|hazards| {
+            if hazards.wumpus == room {
+                Some(WUMPUS)
+            } else if hazards.bats.contains(&room) {
+                Some(BATS)
+            } else if hazards.pits.contains(&room) {
+                Some(PIT)
+            } else {
+                None
+            }
+        });
+        let fill = match (hazard, map.seen.contains_key(&room)) {
+            (Some(hazard), _) => hazard,
+            _ if room == map.room => CURRENT,
+            (None, true) => VISITED,
+            (None, false) => UNVISITED,
+        };
+        canvas.disc(center, radius, fill);
+        if room == map.room && hazard.is_some() {
+            canvas.ring(center, radius, CURRENT);
+        } else if map.hunters.contains(&room) {
+            canvas.ring(center, radius, HUNTER);
+        }
+
+        let scale = if radius >= 12. { 2 } else { 1 };
+        let text = match fill {
+            VISITED | CURRENT => DARK_TEXT,
+            _ => LIGHT_TEXT,
+        };
+        canvas.number(center, room, scale, text);
+
+        if let Some(senses) = map.seen.get(&room) {
+            let dot = (radius / 3.).max(2.);
+            let noticed = [
+                (senses.wumpus, WUMPUS, -135f32),
+                (senses.bats, BATS, -90.),
+                (senses.draft, PIT, -45.),
+            ];
+            for (_, color, angle) in noticed.iter().filter(|(noticed, ..)| *noticed) {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let offset = radius + dot;
+                canvas.disc(
+                    (center.0 + cos * offset, center.1 + sin * offset),
+                    dot,
+                    *color,
+                );
+            }
+        }
+    }
+
+    canvas.encode()
+}
+
+/// Where each room goes on the map, in pixels.
+fn positions(map: &Map) -> Vec<(f32, f32)> {
+    let rooms = map.cave.len();
+    let middle = SIZE as f32 / 2.;
+    let around = |radius: f32, turns: f32| {
+        let (sin, cos) = (turns * std::f32::consts::TAU).sin_cos();
+        // room 0 is at the top
+        (middle + sin * radius, middle - cos * radius)
+    };
+    match map.layout {
+        // the Schlegel diagram: rooms 0 to 4 around the outside, 15 to 19 in the middle, and the
+        // rest in a ring between them
+        Layout::Dodecahedron => (0..rooms)
+            .map(|room| match room {
+                0..=4 => around(220., room as f32 / 5.),
+                5..=14 => around(150., (room as f32 - 7.) / 10.),
+                _ => around(70., (room as f32 - 16.) / 5. - 0.1),
+            })
+            .collect(),
+        // a grid whose edges wrap around, like the torus itself
+        Layout::Torus => {
+            let columns = rooms / TORUS_ROWS;
+            (0..rooms)
+                .map(|room| {
+                    let (row, column) = (room / columns, room % columns);
+                    (
+                        (column as f32 + 0.5) * SIZE as f32 / columns as f32,
+                        (row as f32 + 0.5) * SIZE as f32 / TORUS_ROWS as f32,
+                    )
+                })
+                .collect()
+        }
+        Layout::Mobius | Layout::Random => (0..rooms)
+            .map(|room| around(220., room as f32 / rooms as f32))
+            .collect(),
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+struct Canvas {
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self {
+            pixels: BACKGROUND.repeat(SIZE * SIZE),
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32, color: Color) {
+        if (0..SIZE as i32).contains(&x) && (0..SIZE as i32).contains(&y) {
+            let i = (y as usize * SIZE + x as usize) * 3;
+            self.pixels[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    /// A tunnel between two rooms. On maps that wrap around, tunnels across the edge of the
+    /// map are drawn off the edge from both ends.
+    fn tunnel(&mut self, from: (f32, f32), to: (f32, f32), wraps: bool) {
+        let wrap = |d: f32| match d {
+            d if wraps && d > SIZE as f32 / 2. => -(SIZE as f32),
+            d if wraps && d < -(SIZE as f32) / 2. => SIZE as f32,
+            _ => 0.,
+        };
+        let (dx, dy) = (wrap(to.0 - from.0), wrap(to.1 - from.1));
+        self.line(from, (to.0 + dx, to.1 + dy));
+        if (dx, dy) != (0., 0.) {
+            self.line(to, (from.0 - dx, from.1 - dy));
+        }
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let steps = distance(from, to).ceil().max(1.);
+        for step in 0..=steps as i32 {
+            let t = step as f32 / steps;
+            let x = (from.0 + (to.0 - from.0) * t).round() as i32;
+            let y = (from.1 + (to.1 - from.1) * t).round() as i32;
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                self.set(x + dx, y + dy, TUNNEL);
+            }
+        }
+    }
+
+    fn disc(&mut self, center: (f32, f32), radius: f32, color: Color) {
+        self.fill(center, radius, |_| true, color);
+    }
+
+    fn ring(&mut self, center: (f32, f32), radius: f32, color: Color) {
+        let width = (radius / 5.).max(2.);
+        self.fill(center, radius, |d| d >= radius - width, color);
+    }
+
+    /// Colors the pixels within `radius` of `center` whose distance from it passes `within`.
+    fn fill(
+        &mut self,
+        center: (f32, f32),
+        radius: f32,
+        within: impl Fn(f32) -> bool,
+        color: Color,
+    ) {
+        let reach = radius.ceil() as i32;
+        let (cx, cy) = (center.0.round() as i32, center.1.round() as i32);
+        for y in cy - reach..=cy + reach {
+            for x in cx - reach..=cx + reach {
+                let d = distance((x as f32, y as f32), center);
+                if d <= radius && within(d) {
+                    self.set(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Writes `number` centered on `center`, with each pixel of the font `scale` pixels wide.
+    fn number(&mut self, center: (f32, f32), number: usize, scale: i32, color: Color) {
+        let digits = number.to_string();
+        let width = digits.len() as i32 * 4 * scale - scale;
+        let left = center.0.round() as i32 - width / 2;
+        let top = center.1.round() as i32 - 5 * scale / 2;
+        for (i, digit) in digits.bytes().enumerate() {
+            let glyph = DIGITS[(digit - b'0') as usize];
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) == 0 {
+                        continue;
+                    }
+                    let x = left + (i as i32 * 4 + column) * scale;
+                    let y = top + row as i32 * scale;
+                    for (dx, dy) in (0..scale).flat_map(|dx| (0..scale).map(move |dy| (dx, dy))) {
+                        self.set(x + dx, y + dy, color);
+                    }
+                }
+            }
+        }
+    }
+
+    fn encode(self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut buffer = vec![];
+        let mut encoder = png::Encoder::new(&mut buffer, SIZE as _, SIZE as _);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wumpus::{HuntTheWumpus, Settings};
+
+    fn decodes(png: &[u8]) {
+        let decoder = png::Decoder::new(png);
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (SIZE as u32, SIZE as u32));
+    }
+
+    #[test]
+    fn renders_every_layout() {
+        let layouts = [
+            Layout::Dodecahedron,
+            Layout::Torus,
+            Layout::Mobius,
+            Layout::Random,
+        ];
+        for layout in layouts {
+            for dark in [false, true] {
+                let settings = Settings {
+                    layout,
+                    dark,
+                    ..Settings::default()
+                };
+                let mut game: HuntTheWumpus = HuntTheWumpus::seeded(settings, &[1], 7).unwrap();
+                decodes(&render(&game.map(1)).unwrap());
+                // spend every arrow, so that the whole cave is shown
+                while game.is_running() {
+                    let room = game.tunnels()[0];
+                    assert!(game.shoot(room, 1));
+                }
+                decodes(&render(&game.map(1)).unwrap());
+            }
+        }
+    }
+}
@@ -1,4 +1,16 @@
 use db::models::{JeopardyCategory, JeopardyQuestion};
+use serenity::builder::{CreateEmbed, CreateInteractionResponse};
+use serenity::model::application::component::{ActionRowComponent, ButtonStyle, InputTextStyle};
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction,
+    message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+    InteractionResponseType,
+};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+
+pub mod daily;
+mod game;
 
 pub fn print(category: &JeopardyCategory, questions: &[JeopardyQuestion]) -> String {
     use std::fmt::Write;
@@ -10,14 +22,18 @@ pub fn print(category: &JeopardyCategory, questions: &[JeopardyQuestion]) -> Str
     )
     .unwrap();
     for question in questions {
-        let value = question
-            .value
-            .map(|v| format!("${}", v).into())
-            .unwrap_or(std::borrow::Cow::Borrowed("Unknown"));
+        let daily_double = if question.is_daily_double {
+            " (Daily Double)"
+        } else {
+            ""
+        };
         writeln!(
             &mut out,
-            "{} - {} - ||{}||",
-            value, question.question, question.answer
+            "{}{} - {} - ||{}||",
+            format_value(question.value),
+            daily_double,
+            question.question,
+            question.answer
         )
         .unwrap();
     }
@@ -25,7 +41,1361 @@ pub fn print(category: &JeopardyCategory, questions: &[JeopardyQuestion]) -> Str
     out
 }
 
+fn format_value(value: Option<i32>) -> std::borrow::Cow<'static, str> {
+    value
+        .map(|v| format!("${}", v).into())
+        .unwrap_or(std::borrow::Cow::Borrowed("Unknown"))
+}
+
+fn format_score(score: i64) -> String {
+    if score < 0 {
+        format!("-${}", -score)
+    } else {
+        format!("${}", score)
+    }
+}
+
 pub enum Vote {
     Up,
     Down,
 }
+
+impl Vote {
+    /// How the vote is stored in the database.
+    pub fn direction(&self) -> i16 {
+        match self {
+            Vote::Up => 1,
+            Vote::Down => -1,
+        }
+    }
+}
+
+/// How a category is chosen for `/jeopardy random`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// Any category at all.
+    Random,
+    /// Skips categories already posted in the guild and ones that were voted down.
+    Fresh,
+    /// Favours categories that were voted up, falling back to fresh ones.
+    Rated,
+}
+
+impl std::str::FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Selection::Random),
+            "fresh" => Ok(Selection::Fresh),
+            "rated" => Ok(Selection::Rated),
+            _ => Err(format!("Unknown selection: {}", s)),
+        }
+    }
+}
+
+/// Picks a category matching `filter` with a full set of clues for its round.
+pub fn random_category(
+    source: &dyn db::source::JeopardySource,
+    selection: Selection,
+    guild_id: Option<GuildId>,
+    filter: db::filter::CategoryFilter,
+) -> eyre::Result<(JeopardyCategory, Vec<JeopardyQuestion>)> {
+    let filter = filter.complete();
+    let fresh = match guild_id {
+        Some(guild_id) => filter.clone().without_downvoted().unposted_in(guild_id.0),
+        None => filter.clone().without_downvoted(),
+    };
+    let result = match selection {
+        Selection::Random => source.filtered_category(&filter)?,
+        Selection::Fresh => source.filtered_category(&fresh)?,
+        Selection::Rated => match source.filtered_category(&fresh.clone().rated())? {
+            Some(found) => Some(found),
+            None => source.filtered_category(&fresh)?,
+        },
+    };
+    result.ok_or_else(|| eyre::eyre!("No categories match."))
+}
+
+/// The years the `from` and `to` options can be. The show first aired in 1964.
+pub const FIRST_YEAR: i32 = 1964;
+pub const LAST_YEAR: i32 = 9999;
+
+/// The category filter described by the options of a `/jeopardy` subcommand.
+pub fn category_filter(command: &ApplicationCommandInteraction) -> db::filter::CategoryFilter {
+    let options = command
+        .data
+        .options
+        .first()
+        .map(|sub| sub.options.as_slice())
+        .unwrap_or_default();
+    let mut filter = db::filter::CategoryFilter::new();
+    for option in options {
+        let value = match option.value.as_ref() {
+            Some(value) => value,
+            None => continue,
+        };
+        filter = match (option.name.as_str(), value.as_str(), value.as_i64()) {
+            ("round", Some(round), _) => match round.parse() {
+                Ok(round) => filter.round(round),
+                Err(_) => filter,
+            },
+            ("from", _, Some(year)) => filter.from_year(year as i32),
+            ("to", _, Some(year)) => filter.to_year(year as i32),
+            ("min_value", _, Some(value)) => filter.min_value(value as i32),
+            ("category_contains", Some(text), _) => filter.category_contains(text),
+            _ => filter,
+        };
+    }
+    filter
+}
+
+/// Games in progress, one per channel.
+pub struct Games;
+
+impl TypeMapKey for Games {
+    type Value =
+        std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<ChannelId, game::Game>>>;
+}
+
+const BUZZ: &str = "buzz";
+const SKIP: &str = "skip";
+const ANSWER: &str = "answer";
+const RESPONSE: &str = "response";
+const WAGER: &str = "wager";
+const AMOUNT: &str = "amount";
+/// Final Jeopardy! is shown once everyone has wagered, and revealed once everyone has responded.
+const SHOW: &str = "show";
+const REVEAL: &str = "reveal";
+/// Keeps the history embed well under Discord's limit of 25 fields.
+const HISTORY_SHOWS: i64 = 10;
+/// Keeps exports well under Discord's limit of 8MB for attachments.
+const EXPORT_CATEGORIES: i64 = 100;
+/// Search results are paged with buttons whose custom ids also carry the search text, e.g.
+/// `jeopardy:search:2:potent potables`.
+const SEARCH: &str = "search";
+const SEARCH_PAGE_SIZE: i64 = 5;
+/// Keeps search custom ids under Discord's limit of 100 characters.
+const SEARCH_TEXT_LEN: usize = 64;
+
+/// Component ids look like `jeopardy:buzz:3` so that stale buttons can be told apart.
+fn custom_id(action: &str, clue_index: usize) -> String {
+    format!("{}:{}:{}", crate::JEOPARDY_CMD, action, clue_index)
+}
+
+fn parse_custom_id(custom_id: &str) -> Option<(&str, usize)> {
+    let mut parts = custom_id.split(':').skip(1);
+    let action = parts.next()?;
+    let clue_index = parts.next()?.parse().ok()?;
+    Some((action, clue_index))
+}
+
+fn write_clue(out: &mut String, game: &game::Game) {
+    use std::fmt::Write;
+    let (category, question) = match (game.current_category(), game.current()) {
+        (Some(category), Some(question)) => (category, question),
+        _ => return,
+    };
+    match game.stage() {
+        game::Stage::Open => write!(
+            out,
+            "**{}** for {}\n{}",
+            category.name,
+            format_value(question.value),
+            question.question
+        ),
+        game::Stage::DailyDouble {
+            player,
+            wager: None,
+        } => write!(
+            out,
+            "**Daily Double!** **{}**\n{} wagers before seeing the clue.",
+            category.name,
+            player.map_or("The first player to buzz in".to_owned(), |player| {
+                player.mention().to_string()
+            })
+        ),
+        game::Stage::DailyDouble {
+            player,
+            wager: Some(wager),
+        } => write!(
+            out,
+            "**Daily Double!** **{}** for {}\n{}\n{}, what is your response?",
+            category.name,
+            format_score(*wager as i64),
+            question.question,
+            player
+                .map(|player| player.mention().to_string())
+                .unwrap_or_default()
+        ),
+        game::Stage::Final { shown: false, .. } => write!(
+            out,
+            "**Final Jeopardy!** The category is **{}**.\nMake your wagers, then show the clue.",
+            category.name
+        ),
+        game::Stage::Final { .. } => write!(
+            out,
+            "**Final Jeopardy!** **{}**\n{}\nSubmit your responses, then reveal them.",
+            category.name, question.question
+        ),
+    }
+    .unwrap();
+}
+
+/// A button under a clue, as its action, label and style.
+type ClueButton = (&'static str, &'static str, ButtonStyle);
+
+fn clue_buttons(game: &game::Game) -> Vec<ClueButton> {
+    let (respond, skip) = match game.stage() {
+        game::Stage::Open | game::Stage::DailyDouble { player: None, .. } => (
+            (BUZZ, "Buzz in", ButtonStyle::Primary),
+            (SKIP, "Skip", ButtonStyle::Secondary),
+        ),
+        game::Stage::DailyDouble { wager: None, .. } => (
+            (BUZZ, "Wager", ButtonStyle::Primary),
+            (SKIP, "Skip", ButtonStyle::Secondary),
+        ),
+        game::Stage::DailyDouble { .. } => (
+            (BUZZ, "Respond", ButtonStyle::Primary),
+            (SKIP, "Skip", ButtonStyle::Secondary),
+        ),
+        game::Stage::Final { shown: false, .. } => (
+            (WAGER, "Wager", ButtonStyle::Primary),
+            (SHOW, "Show clue", ButtonStyle::Secondary),
+        ),
+        game::Stage::Final { .. } => (
+            (BUZZ, "Respond", ButtonStyle::Primary),
+            (REVEAL, "Reveal", ButtonStyle::Secondary),
+        ),
+    };
+    vec![respond, skip]
+}
+
+/// Sums up Final Jeopardy! once everyone's responses are revealed.
+fn write_final(out: &mut String, question: &JeopardyQuestion, answers: &[game::Answer]) {
+    use std::fmt::Write;
+    for answer in answers {
+        let response = match answer.response.as_str() {
+            "" => "nothing".to_owned(),
+            response => format!("\"{}\"", response),
+        };
+        let outcome = if answer.correct {
+            format!("right! (+{})", format_score(answer.value as i64))
+        } else {
+            format!("wrong. (-{})", format_score(answer.value as i64))
+        };
+        writeln!(
+            out,
+            "{} said {}, which is {}",
+            answer.user.mention(),
+            response,
+            outcome
+        )
+        .unwrap();
+    }
+    write!(out, "The answer was **{}**.", question.answer).unwrap();
+}
+
+fn write_scores(out: &mut String, game: &game::Game) {
+    use std::fmt::Write;
+    writeln!(out, "Final scores:").unwrap();
+    let scores = game.scores();
+    if scores.is_empty() {
+        writeln!(out, "Nobody played!").unwrap();
+    }
+    for (index, (user, score)) in scores.into_iter().enumerate() {
+        writeln!(
+            out,
+            "{}. {} - {}",
+            index + 1,
+            user.mention(),
+            format_score(score)
+        )
+        .unwrap();
+    }
+}
+
+/// Renders the board as a grid of clue values in a code block, one column per category, with
+/// played clues blanked out. The categories are listed above it by their column numbers, since
+/// embed fields only fit three to a row.
+fn board_embed(game: &game::Game) -> CreateEmbed {
+    use std::fmt::Write;
+
+    const CELL: usize = 6;
+    let mut embed = CreateEmbed::default();
+    let columns = game.columns();
+    if let Some(first) = columns.first() {
+        let category = &first.category;
+        let same_show = columns
+            .iter()
+            .all(|column| column.category.jeopardy_show_number == category.jeopardy_show_number);
+        if same_show {
+            embed.title(format!(
+                "{} - Show #{}",
+                category.round, category.jeopardy_show_number
+            ));
+        } else {
+            embed.title(&category.round);
+        }
+    }
+
+    let mut grid = String::new();
+    for (index, column) in columns.iter().enumerate() {
+        writeln!(grid, "**{}.** {}", index + 1, column.category.name).unwrap();
+    }
+    grid.push_str("```\n");
+    for index in 1..=columns.len() {
+        write!(grid, "{:>width$}", index, width = CELL).unwrap();
+    }
+    let rows = columns.iter().map(|column| column.values.len()).max();
+    for row in 0..rows.unwrap_or_default() {
+        grid.push('\n');
+        for column in columns {
+            let cell = match (column.values.get(row), column.played.get(row)) {
+                (Some(_), Some(true)) => "-".into(),
+                (Some(Some(value)), _) => format!("${}", value),
+                (Some(None), _) => "?".into(),
+                (None, _) => String::new(),
+            };
+            write!(grid, "{:>width$}", cell, width = CELL).unwrap();
+        }
+    }
+    grid.push_str("\n```");
+    embed.description(grid);
+    embed.footer(|footer| footer.text("Pick a clue with /jeopardy pick"));
+    embed
+}
+
+/// What to post after something has happened in a game.
+struct Reply {
+    content: String,
+    /// Attaches buttons for this clue.
+    buttons: Option<(usize, Vec<ClueButton>)>,
+    board: Option<CreateEmbed>,
+    /// Only shown to the player, for Final Jeopardy! wagers and responses.
+    ephemeral: bool,
+}
+
+impl Reply {
+    fn text(content: String) -> Self {
+        Self {
+            content,
+            buttons: None,
+            board: None,
+            ephemeral: false,
+        }
+    }
+
+    fn private(content: String) -> Self {
+        Self {
+            ephemeral: true,
+            ..Self::text(content)
+        }
+    }
+
+    /// The clue that is currently in play.
+    fn clue(game: &game::Game) -> Self {
+        let mut content = String::new();
+        write_clue(&mut content, game);
+        Self {
+            content,
+            buttons: game
+                .current()
+                .map(|_| (game.clue_index(), clue_buttons(game))),
+            board: None,
+            ephemeral: false,
+        }
+    }
+
+    /// Follows `content` with whatever comes next: the next clue, the board, or the final scores.
+    fn next(mut content: String, game: &game::Game) -> Self {
+        content.push_str("\n\n");
+        if game.is_finished() {
+            write_scores(&mut content, game);
+            Self::text(content)
+        } else if game.current().is_some() {
+            write_clue(&mut content, game);
+            Self {
+                content,
+                buttons: Some((game.clue_index(), clue_buttons(game))),
+                board: None,
+                ephemeral: false,
+            }
+        } else {
+            Self {
+                content,
+                buttons: None,
+                board: Some(board_embed(game)),
+                ephemeral: false,
+            }
+        }
+    }
+}
+
+fn clue_response<'a, 'b>(
+    response: &'b mut CreateInteractionResponse<'a>,
+    reply: Reply,
+) -> &'b mut CreateInteractionResponse<'a> {
+    response
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|message| {
+            message.content(reply.content).ephemeral(reply.ephemeral);
+            if let Some(board) = reply.board {
+                message.add_embed(board);
+            }
+            if let Some((clue_index, buttons)) = reply.buttons {
+                message.components(|components| {
+                    components.create_action_row(|row| {
+                        for (action, label, style) in buttons {
+                            row.create_button(|button| {
+                                button
+                                    .custom_id(custom_id(action, clue_index))
+                                    .label(label)
+                                    .style(style)
+                            });
+                        }
+                        row
+                    })
+                });
+            }
+            message
+        })
+}
+
+fn answer_modal<'a, 'b>(
+    response: &'b mut CreateInteractionResponse<'a>,
+    clue_index: usize,
+) -> &'b mut CreateInteractionResponse<'a> {
+    response
+        .kind(InteractionResponseType::Modal)
+        .interaction_response_data(|modal| {
+            modal
+                .custom_id(custom_id(ANSWER, clue_index))
+                .title("What is...")
+                .components(|components| {
+                    components.create_action_row(|row| {
+                        row.create_input_text(|input| {
+                            input
+                                .custom_id(RESPONSE)
+                                .label("Your response")
+                                .style(InputTextStyle::Short)
+                                .required(true)
+                        })
+                    })
+                })
+        })
+}
+
+fn wager_modal<'a, 'b>(
+    response: &'b mut CreateInteractionResponse<'a>,
+    clue_index: usize,
+    (min, max): (i32, i32),
+) -> &'b mut CreateInteractionResponse<'a> {
+    response
+        .kind(InteractionResponseType::Modal)
+        .interaction_response_data(|modal| {
+            modal
+                .custom_id(custom_id(WAGER, clue_index))
+                .title("Make your wager")
+                .components(|components| {
+                    components.create_action_row(|row| {
+                        row.create_input_text(|input| {
+                            input
+                                .custom_id(AMOUNT)
+                                .label(format!("Between ${} and ${}", min, max))
+                                .style(InputTextStyle::Short)
+                                .required(true)
+                        })
+                    })
+                })
+        })
+}
+
+fn ephemeral_response<'a, 'b>(
+    response: &'b mut CreateInteractionResponse<'a>,
+    content: String,
+) -> &'b mut CreateInteractionResponse<'a> {
+    response
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|message| message.content(content).ephemeral(true))
+}
+
+const ALREADY_RUNNING: &str = "There is already a game running in this channel.";
+
+/// Starts `game` in `channel_id` and shows it with `reply`, unless another game was started
+/// there while this one was being picked.
+async fn start(ctx: &Context, channel_id: ChannelId, game: game::Game, reply: Reply) -> Reply {
+    use std::collections::hash_map::Entry;
+
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let mut games = games.lock().await;
+    match games.entry(channel_id) {
+        Entry::Occupied(_) => Reply::private(ALREADY_RUNNING.to_owned()),
+        Entry::Vacant(entry) => {
+            entry.insert(game);
+            reply
+        }
+    }
+}
+
+async fn already_running(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> eyre::Result<bool> {
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let running = games.lock().await.contains_key(&command.channel_id);
+    if running {
+        command
+            .create_interaction_response(&ctx.http, |response| {
+                ephemeral_response(response, ALREADY_RUNNING.to_owned())
+            })
+            .await?;
+    }
+    Ok(running)
+}
+
+pub async fn play(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    if already_running(ctx, &command).await? {
+        return Ok(());
+    }
+
+    let filter = category_filter(&command);
+    let result = tokio::task::block_in_place({
+        let source = crate::get_data::<crate::db_support::Source, _>(ctx).await;
+        move || random_category(source.as_ref(), Selection::Random, None, filter)
+    });
+    let reply = match result {
+        Ok((category, questions)) => {
+            let game = game::Game::new(category, questions);
+            let reply = Reply::clue(&game);
+            start(ctx, command.channel_id, game, reply).await
+        }
+        Err(err) => Reply::text(format!("{}", err)),
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| clue_response(response, reply))
+        .await?;
+    Ok(())
+}
+
+pub async fn board(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    if already_running(ctx, &command).await? {
+        return Ok(());
+    }
+
+    let options = command
+        .data
+        .options
+        .first()
+        .map(|sub| sub.options.as_slice())
+        .unwrap_or_default();
+    let round = options
+        .iter()
+        .find(|option| option.name == "round")
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(::jeopardy::Round::Jeopardy);
+    let mixed = options
+        .iter()
+        .find(|option| option.name == "mixed")
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+
+    let result = tokio::task::block_in_place({
+        let source = crate::get_data::<crate::db_support::Source, _>(ctx).await;
+        move || {
+            if mixed {
+                source.random_board(round)
+            } else {
+                source.random_show_board(round)
+            }
+        }
+    });
+    let reply = match result {
+        Ok(board) => {
+            let game = game::Game::board(board);
+            if game.columns().is_empty() {
+                let reply = Reply::text(format!("Couldn't find any clues for {}.", round.as_str()));
+                command
+                    .create_interaction_response(&ctx.http, |response| {
+                        clue_response(response, reply)
+                    })
+                    .await?;
+                return Ok(());
+            }
+            let reply = if game.current().is_some() {
+                Reply::clue(&game)
+            } else {
+                Reply {
+                    content: String::new(),
+                    buttons: None,
+                    board: Some(board_embed(&game)),
+                    ephemeral: false,
+                }
+            };
+            start(ctx, command.channel_id, game, reply).await
+        }
+        Err(err) => Reply::text(format!("{}", err)),
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| clue_response(response, reply))
+        .await?;
+    Ok(())
+}
+
+pub async fn pick(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let clue = command
+        .data
+        .options
+        .first()
+        .and_then(|sub| sub.options.iter().find(|option| option.name == "clue"))
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or("");
+
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let mut games = games.lock().await;
+    let result = match games.get_mut(&command.channel_id) {
+        Some(game) if game.is_board() => game
+            .find(clue)
+            .and_then(|(column, clue)| game.pick(column, clue, command.user.id).map(|_| ()))
+            .map(|()| Reply::clue(game))
+            .map_err(|err| err.to_string()),
+        _ => Err("There's no board in play in this channel.".to_owned()),
+    };
+    drop(games);
+
+    match result {
+        Ok(reply) => {
+            command
+                .create_interaction_response(&ctx.http, |response| clue_response(response, reply))
+                .await?
+        }
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, err)
+                })
+                .await?
+        }
+    }
+    Ok(())
+}
+
+pub async fn component(ctx: &Context, component: MessageComponentInteraction) -> eyre::Result<()> {
+    let (action, clue_index) = match parse_custom_id(&component.data.custom_id) {
+        Some(parsed) => parsed,
+        None => return Ok(()),
+    };
+    if action == SEARCH {
+        return search_page(ctx, component, clue_index).await;
+    }
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let mut games = games.lock().await;
+    let game = games.get_mut(&component.channel_id);
+
+    match action {
+        BUZZ => {
+            let user = component.user.id;
+            // a Daily Double is wagered on as soon as it's claimed
+            let result = match game {
+                Some(game) => game
+                    .buzz(clue_index, user)
+                    .map(|()| match game.needs_wager(user) {
+                        true => game.wager_range(clue_index, user).ok(),
+                        false => None,
+                    }),
+                None => Err(game::BuzzError::Stale),
+            };
+            drop(games);
+            match result {
+                Ok(Some(range)) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            wager_modal(response, clue_index, range)
+                        })
+                        .await?
+                }
+                Ok(None) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            answer_modal(response, clue_index)
+                        })
+                        .await?
+                }
+                Err(err) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            ephemeral_response(response, err.to_string())
+                        })
+                        .await?
+                }
+            }
+        }
+        SKIP => {
+            let result = match game {
+                Some(game) => game.skip(clue_index).map(|question| {
+                    let content = format!("Skipped! The answer was **{}**.", question.answer);
+                    Reply::next(content, game)
+                }),
+                None => Err(game::BuzzError::Stale),
+            };
+            let finished = take_finished(&mut games, component.channel_id);
+            drop(games);
+            match result {
+                Ok(reply) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            clue_response(response, reply)
+                        })
+                        .await?
+                }
+                Err(err) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            ephemeral_response(response, err.to_string())
+                        })
+                        .await?
+                }
+            }
+            if let Some(game) = finished {
+                record(ctx, &game, component.channel_id, component.guild_id).await?;
+            }
+        }
+        WAGER => {
+            let result = match game {
+                Some(game) => game.wager_range(clue_index, component.user.id),
+                None => Err(game::WagerError::Stale),
+            };
+            drop(games);
+            match result {
+                Ok(range) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            wager_modal(response, clue_index, range)
+                        })
+                        .await?
+                }
+                Err(err) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            ephemeral_response(response, err.to_string())
+                        })
+                        .await?
+                }
+            }
+        }
+        SHOW | REVEAL => {
+            let result = match game {
+                Some(game) if action == SHOW => {
+                    game.show_final(clue_index).map(|()| Reply::clue(game))
+                }
+                Some(game) => game.reveal_final(clue_index).map(|(question, answers)| {
+                    let mut content = String::new();
+                    write_final(&mut content, &question, &answers);
+                    Reply::next(content, game)
+                }),
+                None => Err(game::WagerError::Stale),
+            };
+            let finished = take_finished(&mut games, component.channel_id);
+            drop(games);
+            match result {
+                Ok(reply) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            clue_response(response, reply)
+                        })
+                        .await?
+                }
+                Err(err) => {
+                    component
+                        .create_interaction_response(&ctx.http, |response| {
+                            ephemeral_response(response, err.to_string())
+                        })
+                        .await?
+                }
+            }
+            if let Some(game) = finished {
+                record(ctx, &game, component.channel_id, component.guild_id).await?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn input_value<'a>(modal: &'a ModalSubmitInteraction, custom_id: &str) -> &'a str {
+    modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| row.components.iter())
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == custom_id => {
+                Some(input.value.trim())
+            }
+            _ => None,
+        })
+        .unwrap_or("")
+}
+
+pub async fn modal(ctx: &Context, modal: ModalSubmitInteraction) -> eyre::Result<()> {
+    let (action, clue_index) = match parse_custom_id(&modal.data.custom_id) {
+        Some(parsed @ (ANSWER | WAGER, _)) => parsed,
+        _ => return Ok(()),
+    };
+
+    let games = crate::get_data::<Games, _>(ctx).await;
+    let mut games = games.lock().await;
+    let result = match games.get_mut(&modal.channel_id) {
+        Some(game) if action == WAGER => {
+            let amount = input_value(&modal, AMOUNT)
+                .trim_start_matches('$')
+                .replace(',', "")
+                .parse::<i32>();
+            match amount {
+                Ok(amount) => game
+                    .wager(clue_index, modal.user.id, amount)
+                    .map(|()| match game.stage() {
+                        game::Stage::Final { .. } => Reply::private(format!(
+                            "Your wager of {} is locked in.",
+                            format_score(amount as i64)
+                        )),
+                        _ => Reply::clue(game),
+                    })
+                    .map_err(|err| err.to_string()),
+                Err(_) => Err("Wagers are a whole number of dollars.".to_owned()),
+            }
+        }
+        Some(game) if matches!(game.stage(), game::Stage::Final { .. }) => {
+            let response = input_value(&modal, RESPONSE);
+            game.answer(clue_index, modal.user.id, response)
+                .map(|_| {
+                    Reply::private(format!(
+                        "Your response \"{}\" is locked in until everyone's are revealed.",
+                        response
+                    ))
+                })
+                .map_err(|err| err.to_string())
+        }
+        Some(game) => {
+            let response = input_value(&modal, RESPONSE);
+            game.answer(clue_index, modal.user.id, response)
+                .map(|judgement| match judgement.revealed {
+                    Some(question) if judgement.correct => {
+                        let content = format!(
+                            "{} got it! **{}** (+{})",
+                            modal.user.mention(),
+                            question.answer,
+                            format_score(judgement.value as i64)
+                        );
+                        Reply::next(content, game)
+                    }
+                    Some(question) => {
+                        let content = format!(
+                            "{} said \"{}\". That's incorrect. (-{}) The answer was **{}**.",
+                            modal.user.mention(),
+                            response,
+                            format_score(judgement.value as i64),
+                            question.answer
+                        );
+                        Reply::next(content, game)
+                    }
+                    None => Reply::text(format!(
+                        "{} said \"{}\". That's incorrect. (-{})",
+                        modal.user.mention(),
+                        response,
+                        format_score(judgement.value as i64)
+                    )),
+                })
+                .map_err(|err| err.to_string())
+        }
+        None => Err(game::BuzzError::Stale.to_string()),
+    };
+    let finished = take_finished(&mut games, modal.channel_id);
+    drop(games);
+
+    match result {
+        Ok(reply) => {
+            modal
+                .create_interaction_response(&ctx.http, |response| clue_response(response, reply))
+                .await?
+        }
+        Err(err) => {
+            modal
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, err)
+                })
+                .await?
+        }
+    }
+    if let Some(game) = finished {
+        record(ctx, &game, modal.channel_id, modal.guild_id).await?;
+    }
+    Ok(())
+}
+
+fn take_finished(
+    games: &mut std::collections::HashMap<ChannelId, game::Game>,
+    channel_id: ChannelId,
+) -> Option<game::Game> {
+    match games.get(&channel_id) {
+        Some(game) if game.is_finished() => games.remove(&channel_id),
+        _ => None,
+    }
+}
+
+async fn record(
+    ctx: &Context,
+    game: &game::Game,
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+) -> eyre::Result<()> {
+    // without a database, games are played but not kept
+    let mut db = match crate::get_data::<crate::db_support::DB, _>(ctx).await {
+        Some(db) => db,
+        None => return Ok(()),
+    };
+    let id = uuid::Uuid::new_v4();
+    let answers = game
+        .answers()
+        .iter()
+        .map(|answer| db::models::NewJeopardyGameAnswer {
+            id: uuid::Uuid::new_v4(),
+            jeopardy_game_id: id,
+            jeopardy_question_id: answer.question_id,
+            discord_user_id: answer.user.0 as i64,
+            response: answer.response.as_str(),
+            correct: answer.correct,
+            value: answer.value,
+        })
+        .collect::<Vec<_>>();
+    let new_game = db::models::NewJeopardyGame {
+        id,
+        discord_channel_id: channel_id.0 as i64,
+        discord_guild_id: guild_id.map(|guild_id| guild_id.0 as i64),
+    };
+    tokio::task::block_in_place(move || db.record_jeopardy_game(new_game, &answers))?;
+    Ok(())
+}
+
+pub async fn leaderboard(
+    ctx: &Context,
+    command: ApplicationCommandInteraction,
+) -> eyre::Result<()> {
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return guild_only(ctx, &command).await,
+    };
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        move || crate::db_support::required(db)?.jeopardy_leaderboard(guild_id.0, 10)
+    });
+
+    match result {
+        Ok(players) => {
+            let mut embed = CreateEmbed::default();
+            embed.title("Jeopardy! Leaderboard");
+            if players.is_empty() {
+                embed.description("Nobody has played yet!");
+            } else {
+                let lines = players
+                    .iter()
+                    .enumerate()
+                    .map(|(index, player)| {
+                        format!(
+                            "{}. {} - {} ({} right, {} wrong)",
+                            index + 1,
+                            UserId(player.discord_user_id as u64).mention(),
+                            format_score(player.winnings),
+                            player.correct,
+                            player.incorrect
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                embed.description(lines.join("\n"));
+            }
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| message.add_embed(embed))
+                })
+                .await?;
+        }
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn stats(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+
+    let guild_id = match command.guild_id {
+        Some(guild_id) => guild_id,
+        None => return guild_only(ctx, &command).await,
+    };
+    let user = command
+        .data
+        .options
+        .first()
+        .and_then(|sub| sub.options.iter().find(|option| option.name == "user"))
+        .and_then(|option| match option.resolved.as_ref() {
+            Some(CommandDataOptionValue::User(user, _)) => Some(user.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| command.user.clone());
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        let user_id = user.id.0;
+        move || crate::db_support::required(db)?.jeopardy_player(guild_id.0, user_id)
+    });
+
+    let player = match result {
+        Ok(Some(player)) => player,
+        Ok(None) => {
+            let content = format!("{} hasn't played Jeopardy! here yet.", user.name);
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, content)
+                })
+                .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let answered = player.correct + player.incorrect;
+    let accuracy = if answered > 0 {
+        format!("{:.0}%", player.correct as f64 * 100.0 / answered as f64)
+    } else {
+        "-".to_owned()
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("{}'s Jeopardy! stats", user.name))
+        .thumbnail(user.face())
+        .field("Winnings", format_score(player.winnings), true)
+        .field("Games played", player.games_played, true)
+        .field("Accuracy", accuracy, true)
+        .field("Correct", player.correct, true)
+        .field("Incorrect", player.incorrect, true)
+        .field(
+            "Streak",
+            format!("{} (best {})", player.current_streak, player.best_streak),
+            true,
+        );
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.add_embed(embed))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Handles `/jeopardy history`, listing the categories played on this day in earlier years.
+pub async fn history(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        move || crate::db_support::required(db)?.jeopardy_shows_on_this_day(HISTORY_SHOWS)
+    });
+    let shows = match result {
+        Ok(shows) => shows,
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let mut embed = CreateEmbed::default();
+    match shows.first() {
+        Some((show, _)) => embed.title(format!(
+            "This day in Jeopardy! history: {}",
+            show.air_date.format("%B %-d")
+        )),
+        None => embed
+            .title("This day in Jeopardy! history")
+            .description("No shows aired on this day."),
+    };
+    for (show, categories) in shows {
+        let lines = ::jeopardy::Round::ALL
+            .iter()
+            .filter_map(|round| {
+                let names = categories
+                    .iter()
+                    .filter(|category| category.round == round.as_str())
+                    .map(|category| category.name.as_str())
+                    .collect::<Vec<_>>();
+                (!names.is_empty()).then(|| format!("**{}** {}", round, names.join(", ")))
+            })
+            .collect::<Vec<_>>();
+        embed.field(
+            format!("#{} - {}", show.show_number, show.air_date.format("%Y")),
+            lines.join("\n"),
+            false,
+        );
+    }
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| message.add_embed(embed))
+        })
+        .await?;
+    Ok(())
+}
+
+/// Handles `/jeopardy export`, uploading the top rated categories, those with clues matching a
+/// search, or those from a single show, as a file.
+pub async fn export(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let options = command
+        .data
+        .options
+        .first()
+        .map(|sub| sub.options.as_slice())
+        .unwrap_or_default();
+    let option = |name: &str| {
+        options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_ref())
+    };
+    let format = option("format")
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(::jeopardy::ExportFormat::Csv);
+    let selection = match (
+        option("show").and_then(|value| value.as_i64()),
+        option("search").and_then(|value| value.as_str()),
+    ) {
+        (Some(show_number), _) => db::export::Selection::Show(show_number as i32),
+        (None, Some(text)) => db::export::Selection::Search(text.trim().to_owned()),
+        (None, None) => db::export::Selection::TopRated,
+    };
+
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        let selection = selection.clone();
+        move || -> eyre::Result<Option<Vec<u8>>> {
+            let categories = crate::db_support::required(db)?
+                .export_jeopardy_categories(&selection, EXPORT_CATEGORIES)?;
+            if categories.is_empty() {
+                return Ok(None);
+            }
+            let mut buffer = vec![];
+            ::jeopardy::write_records(&db::export::to_records(&categories)?, format, &mut buffer)
+                .map_err(|err| eyre::eyre!("{}", err))?;
+            Ok(Some(buffer))
+        }
+    });
+    let buffer = match result {
+        Ok(Some(buffer)) => buffer,
+        Ok(None) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, "No categories to export.".to_owned())
+                })
+                .await?;
+            return Ok(());
+        }
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, move |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message.add_file(AttachmentType::Bytes {
+                        data: buffer.into(),
+                        filename: format!("jeopardy-{}.{}", selection.name(), format.extension()),
+                    })
+                })
+        })
+        .await?;
+    Ok(())
+}
+
+async fn guild_only(ctx: &Context, command: &ApplicationCommandInteraction) -> eyre::Result<()> {
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            ephemeral_response(response, "Stats are only kept in servers.".to_owned())
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn search(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let text = command
+        .data
+        .options
+        .first()
+        .and_then(|sub| sub.options.iter().find(|option| option.name == "text"))
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .trim();
+    let text = match text.char_indices().nth(SEARCH_TEXT_LEN) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+    .to_owned();
+
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        let text = text.clone();
+        move || search_results(&mut crate::db_support::required(db)?, &text, 0)
+    });
+    match result {
+        Ok((embed, has_more)) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message.add_embed(embed).components(|components| {
+                                search_buttons(components, &text, 0, has_more)
+                            })
+                        })
+                })
+                .await?
+        }
+        Err(err) => {
+            command
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?
+        }
+    }
+    Ok(())
+}
+
+async fn search_page(
+    ctx: &Context,
+    component: MessageComponentInteraction,
+    page: usize,
+) -> eyre::Result<()> {
+    let text = match component.data.custom_id.splitn(4, ':').nth(3) {
+        Some(text) => text.to_owned(),
+        None => return Ok(()),
+    };
+    let result = tokio::task::block_in_place({
+        let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+        let text = text.clone();
+        move || search_results(&mut crate::db_support::required(db)?, &text, page)
+    });
+    match result {
+        Ok((embed, has_more)) => {
+            component
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|message| {
+                            message.set_embed(embed).components(|components| {
+                                search_buttons(components, &text, page, has_more)
+                            })
+                        })
+                })
+                .await?
+        }
+        Err(err) => {
+            component
+                .create_interaction_response(&ctx.http, |response| {
+                    ephemeral_response(response, format!("{}", err))
+                })
+                .await?
+        }
+    }
+    Ok(())
+}
+
+/// Renders a page of clues matching `text`, and whether there's another page after it.
+fn search_results(db: &mut db::DB, text: &str, page: usize) -> eyre::Result<(CreateEmbed, bool)> {
+    let offset = page as i64 * SEARCH_PAGE_SIZE;
+    // one extra to find out if there's a next page
+    let mut results = db.search_questions(text, offset, SEARCH_PAGE_SIZE + 1)?;
+    let has_more = results.len() as i64 > SEARCH_PAGE_SIZE;
+    results.truncate(SEARCH_PAGE_SIZE as usize);
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("Clues matching \"{}\"", text))
+        .footer(|footer| footer.text(format!("Page {}", page + 1)));
+    if results.is_empty() {
+        embed.description(if page == 0 {
+            "Nothing matched."
+        } else {
+            "No more results."
+        });
+    }
+    for (question, category, show) in results {
+        embed.field(
+            format!("{} for {}", category.name, format_value(question.value)),
+            format!(
+                "{}\n||{}||\n{} - #{}",
+                question.question, question.answer, show.air_date, show.show_number
+            ),
+            false,
+        );
+    }
+    Ok((embed, has_more))
+}
+
+fn search_buttons<'a>(
+    components: &'a mut serenity::builder::CreateComponents,
+    text: &str,
+    page: usize,
+    has_more: bool,
+) -> &'a mut serenity::builder::CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id(format!(
+                    "{}:{}",
+                    custom_id(SEARCH, page.saturating_sub(1)),
+                    text
+                ))
+                .label("Previous")
+                .style(ButtonStyle::Secondary)
+                .disabled(page == 0)
+        })
+        .create_button(|button| {
+            button
+                .custom_id(format!("{}:{}", custom_id(SEARCH, page + 1), text))
+                .label("Next")
+                .style(ButtonStyle::Secondary)
+                .disabled(!has_more)
+        })
+    })
+}
@@ -0,0 +1,210 @@
+//! Clue of the Day: subscribed channels get one clue a day at a set time, with its answer
+//! revealed a few hours later.
+
+use serenity::http::Http;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use std::sync::Arc;
+
+/// How often subscriptions are checked for clues to post and answers to reveal.
+const TICK: std::time::Duration = std::time::Duration::from_secs(60);
+const DEFAULT_REVEAL_AFTER_HOURS: i64 = 4;
+
+/// Handles `/jeopardy daily`, which subscribes the channel, changes when it's posted to, or
+/// with `stop` unsubscribes it. Only members who can manage channels can use it.
+pub async fn subscribe(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let options = command
+        .data
+        .options
+        .first()
+        .map(|sub| sub.options.as_slice())
+        .unwrap_or_default();
+    let option = |name: &str| {
+        options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_ref())
+    };
+    let time = option("time").and_then(|value| value.as_str());
+    let reveal_after = option("reveal_after")
+        .and_then(|value| value.as_i64())
+        .unwrap_or(DEFAULT_REVEAL_AFTER_HOURS);
+    let stop = option("stop")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+
+    let can_manage = matches!(
+        command.member.as_ref().and_then(|member| member.permissions),
+        Some(permissions) if permissions.manage_channels()
+    );
+    let channel_id = command.channel_id.0;
+    let guild_id = command.guild_id.map(|guild_id| guild_id.0);
+    let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+
+    let content = if guild_id.is_none() {
+        "The Clue of the Day can only be posted in servers.".to_owned()
+    } else if !can_manage {
+        "Only members who can manage channels can schedule the Clue of the Day.".to_owned()
+    } else if stop {
+        let result = tokio::task::block_in_place(move || {
+            crate::db_support::required(db)?.unsubscribe_jeopardy_daily(channel_id)
+        });
+        match result {
+            Ok(true) => "No more Clues of the Day will be posted here.".to_owned(),
+            Ok(false) => "This channel doesn't get a Clue of the Day.".to_owned(),
+            Err(err) => err.to_string(),
+        }
+    } else {
+        match time.and_then(parse_time) {
+            None => "Give a time to post at, like 09:30. Times are in UTC.".to_owned(),
+            Some((hour, minute)) => {
+                let result = tokio::task::block_in_place(move || {
+                    crate::db_support::required(db)?.subscribe_jeopardy_daily(
+                        channel_id,
+                        guild_id,
+                        hour,
+                        minute,
+                        reveal_after as i32,
+                    )
+                });
+                match result {
+                    Ok(()) => format!(
+                        "A Clue of the Day will be posted here at {:02}:{:02} UTC, with the answer {} hours later.",
+                        hour, minute, reveal_after
+                    ),
+                    Err(err) => err.to_string(),
+                }
+            }
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            super::ephemeral_response(response, content)
+        })
+        .await?;
+    Ok(())
+}
+
+/// Parses a time of day like "9:30" or "21:05".
+fn parse_time(time: &str) -> Option<(u32, u32)> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let hour = hour.parse().ok().filter(|hour| *hour < 24)?;
+    let minute = minute.parse().ok().filter(|minute| *minute < 60)?;
+    Some((hour, minute))
+}
+
+/// Posts clues and reveals answers as they come due, forever.
+pub async fn run(http: Arc<Http>, db: db::DB) {
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+        if let Err(err) = tick(&http, &db).await {
+            eprintln!("Clue of the Day: {}", err);
+        }
+    }
+}
+
+async fn tick(http: &Arc<Http>, db: &db::DB) -> eyre::Result<()> {
+    // reveal first, so that yesterday's answer comes before today's clue
+    let reveals = tokio::task::block_in_place({
+        let mut db = db.clone();
+        move || db.take_due_jeopardy_daily_reveals()
+    })?;
+    for (channel_id, message_id, question) in reveals {
+        let channel_id = ChannelId(channel_id);
+        let result = channel_id
+            .send_message(http, |message| {
+                message
+                    .content(format!(
+                        "The answer to the Clue of the Day was **{}**.",
+                        question.answer
+                    ))
+                    .reference_message((channel_id, MessageId(message_id)))
+            })
+            .await;
+        if let Err(err) = result {
+            eprintln!(
+                "Couldn't reveal the Clue of the Day in {}: {}",
+                channel_id, err
+            );
+        }
+    }
+
+    let due = tokio::task::block_in_place({
+        let mut db = db.clone();
+        move || db.due_jeopardy_daily_posts()
+    })?;
+    for subscription in due {
+        if let Err(err) = post(http, db, &subscription).await {
+            eprintln!(
+                "Couldn't post the Clue of the Day in {}: {}",
+                subscription.discord_channel_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Posts one clue from a category the guild hasn't seen, and records it like any other
+/// category post so that reactions on it count as votes.
+async fn post(
+    http: &Arc<Http>,
+    db: &db::DB,
+    subscription: &db::models::JeopardyDailySubscription,
+) -> eyre::Result<()> {
+    let channel_id = ChannelId(subscription.discord_channel_id as u64);
+    let guild_id = subscription
+        .discord_guild_id
+        .map(|guild_id| GuildId(guild_id as u64));
+    let (category, questions) = tokio::task::block_in_place(|| {
+        super::random_category(
+            db,
+            super::Selection::Fresh,
+            guild_id,
+            db::filter::CategoryFilter::new(),
+        )
+    })?;
+    // Daily Doubles are worth whatever someone wagered on them, so pick an ordinary clue
+    let question = {
+        use rand::seq::{IteratorRandom, SliceRandom};
+        let rng = &mut rand::thread_rng();
+        questions
+            .iter()
+            .filter(|question| !question.is_daily_double)
+            .choose(rng)
+            .or_else(|| questions.choose(rng))
+            .cloned()
+            .ok_or_else(|| eyre::eyre!("{} has no clues.", category.name))?
+    };
+
+    let content = format!(
+        "**Clue of the Day**\n**{}** for {}\n{}\n\nThe answer will be revealed in {} hours.",
+        category.name,
+        super::format_value(question.value),
+        question.question,
+        subscription.reveal_after_hours
+    );
+    let message = channel_id
+        .send_message(http, |message| message.content(content))
+        .await?;
+    // record the post first, so that nothing going wrong after this posts the clue twice
+    tokio::task::block_in_place({
+        let mut db = db.clone();
+        let message_id = message.id.0;
+        move || -> eyre::Result<()> {
+            db.set_jeopardy_daily_reveal(channel_id.0, message_id, question.id)?;
+            db.record_jeopardy_category_post(
+                category.id,
+                message_id,
+                guild_id.map(|guild_id| guild_id.0),
+            )?;
+            Ok(())
+        }
+    })?;
+    for vote in ['👍', '👎'] {
+        channel_id.create_reaction(http, message.id, vote).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,666 @@
+use db::models::{JeopardyCategory, JeopardyQuestion};
+use serenity::model::id::UserId;
+use serenity::prelude::Mentionable;
+use std::time::{Duration, Instant};
+
+/// How long a player has to submit an answer after buzzing in before the clue reopens.
+const BUZZ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum BuzzError {
+    /// The clue that was buzzed on has already been answered or skipped.
+    Stale,
+    /// Someone else has buzzed in and still has time to answer.
+    Locked(UserId),
+    /// This player has already answered the current clue.
+    AlreadyAttempted,
+    /// The clue needs a wager from this player before they can respond.
+    NoWager,
+}
+
+impl std::fmt::Display for BuzzError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuzzError::Stale => write!(f, "That clue is closed."),
+            BuzzError::Locked(user) => write!(f, "{} has already buzzed in.", user.mention()),
+            BuzzError::AlreadyAttempted => write!(f, "You've already had a go at this clue."),
+            BuzzError::NoWager => write!(f, "You need to make a wager first."),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WagerError {
+    /// The clue isn't taking wagers, or not any more.
+    Stale,
+    /// The Daily Double belongs to someone else.
+    NotYours(UserId),
+    AlreadyWagered,
+    OutOfRange {
+        min: i32,
+        max: i32,
+    },
+    /// Final Jeopardy! can't be shown until someone has wagered.
+    NoWagers,
+}
+
+impl std::fmt::Display for WagerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WagerError::Stale => write!(f, "That clue isn't taking wagers."),
+            WagerError::NotYours(user) => write!(f, "That Daily Double is {}'s.", user.mention()),
+            WagerError::AlreadyWagered => write!(f, "You've already made your wager."),
+            WagerError::OutOfRange { min, max } => {
+                write!(f, "Your wager has to be between ${} and ${}.", min, max)
+            }
+            WagerError::NoWagers => write!(f, "Nobody has made a wager yet."),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PickError {
+    /// A clue is already in play.
+    InProgress,
+    UnknownCategory,
+    UnknownClue,
+    AlreadyPlayed,
+}
+
+impl std::fmt::Display for PickError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PickError::InProgress => write!(f, "Finish the current clue first."),
+            PickError::UnknownCategory => write!(f, "There's no category like that on the board."),
+            PickError::UnknownClue => write!(f, "There's no clue for that amount."),
+            PickError::AlreadyPlayed => write!(f, "That clue has already been played."),
+        }
+    }
+}
+
+/// A single response given by a player during a game.
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub question_id: uuid::Uuid,
+    pub user: UserId,
+    pub response: String,
+    pub correct: bool,
+    pub value: i32,
+}
+
+/// The result of a player's response to the current clue.
+pub struct Judgement {
+    pub correct: bool,
+    pub value: i32,
+    /// The clue that was answered, once it has closed.
+    pub revealed: Option<JeopardyQuestion>,
+}
+
+/// How the clue in play is being played.
+#[derive(Debug, Clone)]
+pub enum Stage {
+    /// Anyone can buzz in.
+    Open,
+    /// Only `player` can respond, once they've made a wager. The player who picked the clue
+    /// gets it, or in single category games the first player to buzz in.
+    DailyDouble {
+        player: Option<UserId>,
+        wager: Option<i32>,
+    },
+    /// Everyone wagers privately before the clue is shown, then responds privately until the
+    /// responses are revealed.
+    Final {
+        wagers: Vec<(UserId, i32)>,
+        responses: Vec<(UserId, String)>,
+        shown: bool,
+    },
+}
+
+/// One category on the board and which of its clues have been played.
+pub struct Column {
+    pub category: JeopardyCategory,
+    pub clues: Vec<JeopardyQuestion>,
+    /// What each clue is worth on the board. Daily Doubles are listed with the wager that was
+    /// made on them, so they're given the value missing from the rest of the column instead.
+    pub values: Vec<Option<i32>>,
+    pub played: Vec<bool>,
+}
+
+impl Column {
+    fn new(category: JeopardyCategory, clues: Vec<JeopardyQuestion>) -> Self {
+        let fixed = clues
+            .iter()
+            .filter(|question| !question.is_daily_double)
+            .filter_map(|question| question.value)
+            .collect::<Vec<_>>();
+        let step = fixed.iter().fold(0, |a, b| gcd(a, *b));
+        let mut missing = (1..=clues.len() as i32)
+            .map(|row| row * step)
+            .filter(|value| *value > 0 && !fixed.contains(value));
+
+        let mut clues = clues
+            .into_iter()
+            .map(|question| match question.is_daily_double {
+                true => (missing.next().or(question.value), question),
+                false => (question.value, question),
+            })
+            .collect::<Vec<_>>();
+        clues.sort_by_key(|(value, _)| value.unwrap_or(i32::MAX));
+        let (values, clues): (Vec<_>, Vec<_>) = clues.into_iter().unzip();
+        let played = vec![false; clues.len()];
+        Self {
+            category,
+            clues,
+            values,
+            played,
+        }
+    }
+
+    fn is_final(&self) -> bool {
+        self.category.round == ::jeopardy::Round::FinalJeopardy.as_str()
+    }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+/// A playable round, revealing one clue at a time.
+///
+/// Single category games move through their clues in order. Games over a full board wait
+/// for players to pick each clue.
+pub struct Game {
+    columns: Vec<Column>,
+    auto_advance: bool,
+    current: Option<(usize, usize)>,
+    stage: Stage,
+    clue_index: usize,
+    buzz: Option<(UserId, Instant)>,
+    attempted: Vec<UserId>,
+    answers: Vec<Answer>,
+}
+
+impl Game {
+    pub fn new(category: JeopardyCategory, questions: Vec<JeopardyQuestion>) -> Self {
+        let mut game = Self::with_columns(vec![Column::new(category, questions)], true);
+        game.next_in_column(0);
+        game
+    }
+
+    /// A game over a full board. A Final Jeopardy! board has nothing to pick, so its clue is
+    /// put straight into play.
+    pub fn board(board: Vec<(JeopardyCategory, Vec<JeopardyQuestion>)>) -> Self {
+        let columns = board
+            .into_iter()
+            .filter(|(_, questions)| !questions.is_empty())
+            .map(|(category, questions)| Column::new(category, questions))
+            .collect::<Vec<_>>();
+        let is_final = !columns.is_empty() && columns.iter().all(Column::is_final);
+        let mut game = Self::with_columns(columns, is_final);
+        if is_final {
+            game.next_in_column(0);
+        }
+        game
+    }
+
+    fn with_columns(columns: Vec<Column>, auto_advance: bool) -> Self {
+        Self {
+            columns,
+            auto_advance,
+            current: None,
+            stage: Stage::Open,
+            clue_index: 0,
+            buzz: None,
+            attempted: vec![],
+            answers: vec![],
+        }
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn is_board(&self) -> bool {
+        !self.auto_advance
+    }
+
+    pub fn clue_index(&self) -> usize {
+        self.clue_index
+    }
+
+    pub fn current(&self) -> Option<&JeopardyQuestion> {
+        let (column, clue) = self.current?;
+        Some(&self.columns[column].clues[clue])
+    }
+
+    pub fn current_category(&self) -> Option<&JeopardyCategory> {
+        let (column, _) = self.current?;
+        Some(&self.columns[column].category)
+    }
+
+    pub fn stage(&self) -> &Stage {
+        &self.stage
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current.is_none()
+            && self
+                .columns
+                .iter()
+                .all(|column| column.played.iter().all(|played| *played))
+    }
+
+    pub fn answers(&self) -> &[Answer] {
+        &self.answers
+    }
+
+    /// Finds the clue described by `pick`, e.g. "Potent Potables for $400". Without an amount
+    /// the cheapest clue left in the category is chosen.
+    pub fn find(&self, pick: &str) -> Result<(usize, usize), PickError> {
+        let (name, value) = match pick.rsplit_once(" for ") {
+            Some((name, value)) => {
+                let value = value.trim().trim_start_matches('$').replace(',', "");
+                match value.parse::<i32>() {
+                    Ok(value) => (name, Some(value)),
+                    Err(_) => (pick, None),
+                }
+            }
+            None => (pick, None),
+        };
+
+        let column_index = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                let confidence = ::jeopardy::answer::confidence(name, &column.category.name);
+                (index, confidence)
+            })
+            .filter(|(_, confidence)| *confidence >= ::jeopardy::answer::THRESHOLD)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+            .ok_or(PickError::UnknownCategory)?;
+
+        let column = &self.columns[column_index];
+        let mut candidates = column
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| value.is_none() || column.values[*index] == value)
+            .map(|(index, _)| index)
+            .peekable();
+        if candidates.peek().is_none() {
+            return Err(PickError::UnknownClue);
+        }
+        candidates
+            .find(|index| !column.played[*index])
+            .map(|index| (column_index, index))
+            .ok_or(PickError::AlreadyPlayed)
+    }
+
+    /// Puts the clue at `column`, `clue` into play. If it's a Daily Double it belongs to `user`.
+    pub fn pick(
+        &mut self,
+        column: usize,
+        clue: usize,
+        user: UserId,
+    ) -> Result<&JeopardyQuestion, PickError> {
+        if self.current.is_some() {
+            return Err(PickError::InProgress);
+        }
+        let played = self
+            .columns
+            .get_mut(column)
+            .and_then(|column| column.played.get_mut(clue))
+            .ok_or(PickError::UnknownClue)?;
+        if *played {
+            return Err(PickError::AlreadyPlayed);
+        }
+        *played = true;
+        self.start(column, clue, Some(user));
+        Ok(&self.columns[column].clues[clue])
+    }
+
+    /// Claims the current clue for `user` so that they can submit an answer.
+    pub fn buzz(&mut self, clue_index: usize, user: UserId) -> Result<(), BuzzError> {
+        if clue_index != self.clue_index || self.current.is_none() {
+            return Err(BuzzError::Stale);
+        }
+        match &mut self.stage {
+            Stage::Open => {}
+            Stage::DailyDouble { player, .. } => {
+                return match *player.get_or_insert(user) {
+                    holder if holder == user => Ok(()),
+                    holder => Err(BuzzError::Locked(holder)),
+                }
+            }
+            Stage::Final {
+                wagers,
+                responses,
+                shown,
+            } => {
+                return if !*shown {
+                    Err(BuzzError::Stale)
+                } else if !wagers.iter().any(|(player, _)| *player == user) {
+                    Err(BuzzError::NoWager)
+                } else if responses.iter().any(|(player, _)| *player == user) {
+                    Err(BuzzError::AlreadyAttempted)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+        if self.attempted.contains(&user) {
+            return Err(BuzzError::AlreadyAttempted);
+        }
+        match self.buzz {
+            Some((holder, at)) if holder != user && at.elapsed() < BUZZ_TIMEOUT => {
+                Err(BuzzError::Locked(holder))
+            }
+            _ => {
+                self.buzz = Some((user, Instant::now()));
+                Ok(())
+            }
+        }
+    }
+
+    /// The smallest and largest wagers `user` can make on the clue at `clue_index`. On a Daily
+    /// Double players can always wager up to the top value on the board, or their score if it's
+    /// higher. In Final Jeopardy! they can only wager what they have.
+    pub fn wager_range(&self, clue_index: usize, user: UserId) -> Result<(i32, i32), WagerError> {
+        if clue_index != self.clue_index || self.current.is_none() {
+            return Err(WagerError::Stale);
+        }
+        let (min, is_final) = match &self.stage {
+            Stage::Open => return Err(WagerError::Stale),
+            Stage::DailyDouble { player, wager } => {
+                match player {
+                    Some(player) if *player != user => return Err(WagerError::NotYours(*player)),
+                    None => return Err(WagerError::Stale),
+                    Some(_) => {}
+                }
+                if wager.is_some() {
+                    return Err(WagerError::AlreadyWagered);
+                }
+                (5, false)
+            }
+            Stage::Final { shown: true, .. } => return Err(WagerError::Stale),
+            Stage::Final { .. } => (0, true),
+        };
+        let score = self
+            .scores()
+            .into_iter()
+            .find(|(player, _)| *player == user)
+            .map_or(0, |(_, score)| score.clamp(0, i32::MAX as i64) as i32);
+        if is_final {
+            return Ok((min, score));
+        }
+        let top_value = self
+            .columns
+            .iter()
+            .flat_map(|column| column.values.iter().flatten())
+            .max()
+            .copied()
+            .unwrap_or(1000);
+        Ok((min, top_value.max(score)))
+    }
+
+    /// Records a wager from `user` on the clue at `clue_index`. Final Jeopardy! wagers can be
+    /// changed until the clue is shown.
+    pub fn wager(
+        &mut self,
+        clue_index: usize,
+        user: UserId,
+        amount: i32,
+    ) -> Result<(), WagerError> {
+        let (min, max) = self.wager_range(clue_index, user)?;
+        if amount < min || amount > max {
+            return Err(WagerError::OutOfRange { min, max });
+        }
+        match &mut self.stage {
+            Stage::Open => return Err(WagerError::Stale),
+            Stage::DailyDouble { wager, .. } => *wager = Some(amount),
+            Stage::Final { wagers, .. } => {
+                wagers.retain(|(player, _)| *player != user);
+                wagers.push((user, amount));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `user` has to wager before they can respond to the current clue.
+    pub fn needs_wager(&self, user: UserId) -> bool {
+        match &self.stage {
+            Stage::Open => false,
+            Stage::DailyDouble { player, wager } => *player == Some(user) && wager.is_none(),
+            Stage::Final { wagers, .. } => !wagers.iter().any(|(player, _)| *player == user),
+        }
+    }
+
+    /// Shows the Final Jeopardy! clue, after which wagers are locked in.
+    pub fn show_final(&mut self, clue_index: usize) -> Result<(), WagerError> {
+        if clue_index != self.clue_index {
+            return Err(WagerError::Stale);
+        }
+        match &mut self.stage {
+            Stage::Final { shown: true, .. } | Stage::Open | Stage::DailyDouble { .. } => {
+                return Err(WagerError::Stale)
+            }
+            Stage::Final { wagers, .. } if wagers.is_empty() => return Err(WagerError::NoWagers),
+            Stage::Final { shown, .. } => *shown = true,
+        }
+        Ok(())
+    }
+
+    /// Judges everyone's Final Jeopardy! responses at once and closes the clue. Players who
+    /// wagered but never responded lose their wager.
+    pub fn reveal_final(
+        &mut self,
+        clue_index: usize,
+    ) -> Result<(JeopardyQuestion, Vec<Answer>), WagerError> {
+        if clue_index != self.clue_index {
+            return Err(WagerError::Stale);
+        }
+        let (wagers, responses) = match &mut self.stage {
+            Stage::Final {
+                wagers,
+                responses,
+                shown: true,
+            } => (std::mem::take(wagers), std::mem::take(responses)),
+            _ => return Err(WagerError::Stale),
+        };
+        let question = self.current().ok_or(WagerError::Stale)?;
+        let answers = wagers
+            .into_iter()
+            .map(|(user, wager)| {
+                let response = responses
+                    .iter()
+                    .find(|(player, _)| *player == user)
+                    .map(|(_, response)| response.clone())
+                    .unwrap_or_default();
+                Answer {
+                    question_id: question.id,
+                    user,
+                    correct: ::jeopardy::answer::is_match(&response, &question.answer),
+                    response,
+                    value: wager,
+                }
+            })
+            .collect::<Vec<_>>();
+        self.answers.extend(answers.iter().cloned());
+        Ok((self.advance(), answers))
+    }
+
+    /// Judges `response` from `user`, who must be holding the buzz on `clue_index`.
+    ///
+    /// A correct response closes the clue and moves on to the next one. An incorrect
+    /// response reopens the clue for everyone who hasn't tried it yet, and closes it once
+    /// everyone who played the earlier clues has missed it. On a Daily Double the wager
+    /// is won or lost and the clue closes either way. Final Jeopardy! responses are only
+    /// recorded here, and judged by [`Game::reveal_final`].
+    pub fn answer(
+        &mut self,
+        clue_index: usize,
+        user: UserId,
+        response: &str,
+    ) -> Result<Judgement, BuzzError> {
+        if clue_index != self.clue_index {
+            return Err(BuzzError::Stale);
+        }
+        match &mut self.stage {
+            Stage::Open => {}
+            Stage::DailyDouble { player, wager } => {
+                match *player {
+                    Some(holder) if holder == user => {}
+                    Some(holder) => return Err(BuzzError::Locked(holder)),
+                    None => return Err(BuzzError::Stale),
+                }
+                let value = wager.ok_or(BuzzError::NoWager)?;
+                let question = self.current().ok_or(BuzzError::Stale)?;
+                let correct = ::jeopardy::answer::is_match(response, &question.answer);
+                self.answers.push(Answer {
+                    question_id: question.id,
+                    user,
+                    response: response.to_owned(),
+                    correct,
+                    value,
+                });
+                let revealed = Some(self.advance());
+                return Ok(Judgement {
+                    correct,
+                    value,
+                    revealed,
+                });
+            }
+            Stage::Final {
+                wagers,
+                responses,
+                shown,
+            } => {
+                return if !*shown {
+                    Err(BuzzError::Stale)
+                } else if !wagers.iter().any(|(player, _)| *player == user) {
+                    Err(BuzzError::NoWager)
+                } else if responses.iter().any(|(player, _)| *player == user) {
+                    Err(BuzzError::AlreadyAttempted)
+                } else {
+                    responses.push((user, response.to_owned()));
+                    Ok(Judgement {
+                        correct: false,
+                        value: 0,
+                        revealed: None,
+                    })
+                }
+            }
+        }
+        match self.buzz {
+            Some((holder, _)) if holder == user => {}
+            Some((holder, at)) if at.elapsed() < BUZZ_TIMEOUT => {
+                return Err(BuzzError::Locked(holder))
+            }
+            _ => return Err(BuzzError::Stale),
+        }
+        let question = self.current().ok_or(BuzzError::Stale)?;
+        let correct = ::jeopardy::answer::is_match(response, &question.answer);
+        let value = question.value.unwrap_or(0);
+        let question_id = question.id;
+        self.answers.push(Answer {
+            question_id,
+            user,
+            response: response.to_owned(),
+            correct,
+            value,
+        });
+        self.buzz = None;
+        self.attempted.push(user);
+
+        // the clue closes once everyone who played the earlier clues has missed it. The first
+        // clue has nobody to wait for, so it stays open to whoever wants to join in.
+        let mut players = self
+            .answers
+            .iter()
+            .filter(|answer| answer.question_id != question_id)
+            .map(|answer| answer.user)
+            .peekable();
+        let exhausted =
+            players.peek().is_some() && players.all(|player| self.attempted.contains(&player));
+        let revealed = (correct || exhausted).then(|| self.advance());
+        Ok(Judgement {
+            correct,
+            value,
+            revealed,
+        })
+    }
+
+    /// Gives up on the clue at `clue_index`, returning it so its answer can be shown.
+    pub fn skip(&mut self, clue_index: usize) -> Result<JeopardyQuestion, BuzzError> {
+        if clue_index != self.clue_index || self.current.is_none() {
+            return Err(BuzzError::Stale);
+        }
+        Ok(self.advance())
+    }
+
+    /// Each player's winnings, highest first.
+    pub fn scores(&self) -> Vec<(UserId, i64)> {
+        let mut scores = Vec::<(UserId, i64)>::new();
+        for answer in self.answers.iter() {
+            let delta = if answer.correct {
+                answer.value as i64
+            } else {
+                -(answer.value as i64)
+            };
+            match scores.iter_mut().find(|(user, _)| *user == answer.user) {
+                Some((_, score)) => *score += delta,
+                None => scores.push((answer.user, delta)),
+            }
+        }
+        scores.sort_by(|(_, a), (_, b)| b.cmp(a));
+        scores
+    }
+
+    fn advance(&mut self) -> JeopardyQuestion {
+        let (column, clue) = self.current.take().expect("no clue in play");
+        let question = self.columns[column].clues[clue].clone();
+        self.clue_index += 1;
+        self.stage = Stage::Open;
+        self.buzz = None;
+        self.attempted.clear();
+        if self.auto_advance {
+            self.next_in_column(column);
+        }
+        question
+    }
+
+    fn next_in_column(&mut self, column: usize) {
+        let next = self.columns[column]
+            .played
+            .iter()
+            .position(|played| !played);
+        if let Some(clue) = next {
+            self.columns[column].played[clue] = true;
+            self.start(column, clue, None);
+        }
+    }
+
+    /// Puts a clue into play. A Daily Double belongs to `picker`, if anyone picked it.
+    fn start(&mut self, column: usize, clue: usize, picker: Option<UserId>) {
+        self.current = Some((column, clue));
+        self.stage = if self.columns[column].is_final() {
+            Stage::Final {
+                wagers: vec![],
+                responses: vec![],
+                shown: false,
+            }
+        } else if self.columns[column].clues[clue].is_daily_double {
+            Stage::DailyDouble {
+                player: picker,
+                wager: None,
+            }
+        } else {
+            Stage::Open
+        };
+    }
+}
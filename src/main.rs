@@ -1,15 +1,14 @@
-use crate::jeopardy::Vote;
 use serenity::builder::CreateEmbed;
 use serenity::model::application::command::{Command as ApplicationCommand, CommandOptionType};
 use serenity::model::application::interaction::{
-    application_command::{ApplicationCommandInteraction, CommandDataOption},
-    Interaction, InteractionResponseType,
+    application_command::ApplicationCommandInteraction, Interaction, InteractionResponseType,
 };
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 
 // mod grim;
 mod db_support;
+mod htw;
 mod images;
 mod jeopardy;
 mod roll;
@@ -19,6 +18,13 @@ mod wumpus;
 
 struct Handler;
 
+/// The bot's own user, so that the votes it seeds under Clue of the Day posts aren't counted.
+struct BotUser;
+
+impl TypeMapKey for BotUser {
+    type Value = UserId;
+}
+
 const JEOPARDY_CMD: &'static str = "jeopardy";
 const RAYZ_CMD: &'static str = "rayz";
 const WUMPUS_CMD: &'static str = "htw";
@@ -33,6 +39,53 @@ impl EventHandler for Handler {
     /// Provides data about the bot and the guilds it's in.
     async fn ready(&self, ctx: Context, data: Ready) {
         println!("BOT READY");
+        ctx.data.write().await.insert::<BotUser>(data.user.id);
+
+        /// Options that narrow down which categories a jeopardy subcommand can choose from.
+        fn category_filter_options(
+            option: &mut serenity::builder::CreateApplicationCommandOption,
+        ) -> &mut serenity::builder::CreateApplicationCommandOption {
+            option
+                .create_sub_option(|option| {
+                    option
+                        .name("round")
+                        .description("Only categories from this round.")
+                        .kind(CommandOptionType::String)
+                        .add_string_choice("Jeopardy!", "Jeopardy!")
+                        .add_string_choice("Double Jeopardy!", "Double Jeopardy!")
+                        .add_string_choice("Final Jeopardy!", "Final Jeopardy!")
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("from")
+                        .description("Only shows that aired in or after this year.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(jeopardy::FIRST_YEAR)
+                        .max_int_value(jeopardy::LAST_YEAR)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("to")
+                        .description("Only shows that aired in or before this year.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(jeopardy::FIRST_YEAR)
+                        .max_int_value(jeopardy::LAST_YEAR)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("min_value")
+                        .description("Only categories whose clues are all worth at least this.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(0)
+                        .max_int_value(i32::MAX)
+                })
+                .create_sub_option(|option| {
+                    option
+                        .name("category_contains")
+                        .description("Only categories with this in their name.")
+                        .kind(CommandOptionType::String)
+                })
+        }
 
         fn create_interactions(
             commands: &mut serenity::builder::CreateApplicationCommands,
@@ -41,7 +94,156 @@ impl EventHandler for Handler {
                 .create_application_command(|command| {
                     command
                         .name(JEOPARDY_CMD)
-                        .description("Displays a random jeopardy category.")
+                        .description("Plays Jeopardy!")
+                        .create_option(|option| {
+                            option
+                                .name("random")
+                                .description("Displays a random jeopardy category.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("selection")
+                                        .description("How the category is chosen.")
+                                        .kind(CommandOptionType::String)
+                                        .add_string_choice("Anything", "random")
+                                        .add_string_choice("Not posted here yet", "fresh")
+                                        .add_string_choice("Highly rated", "rated")
+                                });
+                            category_filter_options(option)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("play")
+                                .description("Play through a random category, one clue at a time.")
+                                .kind(CommandOptionType::SubCommand);
+                            category_filter_options(option)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("board")
+                                .description("Play a full board for a round.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("round")
+                                        .description("The round to play.")
+                                        .kind(CommandOptionType::String)
+                                        .add_string_choice("Jeopardy!", "Jeopardy!")
+                                        .add_string_choice("Double Jeopardy!", "Double Jeopardy!")
+                                        .add_string_choice("Final Jeopardy!", "Final Jeopardy!")
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("mixed")
+                                        .description("Assemble the board from different shows.")
+                                        .kind(CommandOptionType::Boolean)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("pick")
+                                .description("Pick a clue from the board.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("clue")
+                                        .description("e.g. Potent Potables for $400")
+                                        .kind(CommandOptionType::String)
+                                        .required(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("leaderboard")
+                                .description("Shows the top Jeopardy! players in this server.")
+                                .kind(CommandOptionType::SubCommand)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("stats")
+                                .description("Shows a player's Jeopardy! stats in this server.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("Defaults to you.")
+                                        .kind(CommandOptionType::User)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("search")
+                                .description("Searches the clue archive.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("text")
+                                        .description("Words or \"a phrase\" to look for.")
+                                        .kind(CommandOptionType::String)
+                                        .required(true)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("history")
+                                .description("Categories played on this day in past years.")
+                                .kind(CommandOptionType::SubCommand)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("daily")
+                                .description("Posts a Clue of the Day in this channel.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("time")
+                                        .description("When to post each day, like 09:30, in UTC.")
+                                        .kind(CommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("reveal_after")
+                                        .description("How many hours until the answer is revealed.")
+                                        .kind(CommandOptionType::Integer)
+                                        .min_int_value(1)
+                                        .max_int_value(23)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("stop")
+                                        .description("Stop posting here.")
+                                        .kind(CommandOptionType::Boolean)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("export")
+                                .description("Exports categories and their clues as a file. By default, the top rated ones.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("format")
+                                        .description("The kind of file.")
+                                        .kind(CommandOptionType::String)
+                                        .required(true)
+                                        .add_string_choice("CSV", "csv")
+                                        .add_string_choice("JSON", "json")
+                                        .add_string_choice("Anki notes", "anki")
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("search")
+                                        .description("Only categories with clues matching these words.")
+                                        .kind(CommandOptionType::String)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("show")
+                                        .description("Only categories from the show with this number.")
+                                        .kind(CommandOptionType::Integer)
+                                        .min_int_value(1)
+                                })
+                        })
                 })
                 .create_application_command(|command| {
                     command
@@ -64,6 +266,64 @@ impl EventHandler for Handler {
                                 .name("start")
                                 .description("Start a new game of Hunt The Wumpus")
                                 .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("rules")
+                                        .description("Which version of the game to play.")
+                                        .kind(CommandOptionType::String)
+                                        .add_string_choice("Current", "current")
+                                        .add_string_choice("Classic (1973)", "classic")
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("cave")
+                                        .description("The shape of the cave.")
+                                        .kind(CommandOptionType::String)
+                                        .add_string_choice("Dodecahedron", "dodecahedron")
+                                        .add_string_choice("Torus", "torus")
+                                        .add_string_choice("Möbius strip", "mobius")
+                                        .add_string_choice("Random", "random")
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("rooms")
+                                        .description("How many rooms the cave has. Dodecahedrons always have 20.")
+                                        .kind(CommandOptionType::Integer)
+                                        .min_int_value(wumpus::MIN_ROOMS)
+                                        .max_int_value(wumpus::MAX_ROOMS)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("dark")
+                                        .description("Hide where tunnels lead.")
+                                        .kind(CommandOptionType::Boolean)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("together")
+                                        .description("Share the cave with everyone in this channel, who can /htw join.")
+                                        .kind(CommandOptionType::Boolean)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("mode")
+                                        .description("In a shared cave, whether everyone wins or only whoever kills the Wumpus.")
+                                        .kind(CommandOptionType::String)
+                                        .add_string_choice("Co-op", "coop")
+                                        .add_string_choice("Versus", "versus")
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("join")
+                                .description("Join the shared game of Hunt The Wumpus in this channel.")
+                                .kind(CommandOptionType::SubCommand)
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("leave")
+                                .description("Leave the shared game of Hunt The Wumpus in this channel.")
+                                .kind(CommandOptionType::SubCommand)
                         })
                         .create_option(|option| {
                             option
@@ -73,6 +333,12 @@ impl EventHandler for Handler {
                                 )
                                 .kind(CommandOptionType::SubCommand)
                         })
+                        .create_option(|option| {
+                            option
+                                .name("map")
+                                .description("Shows a map of the cave as far as you've explored it.")
+                                .kind(CommandOptionType::SubCommand)
+                        })
                         .create_option(|option| {
                             option
                                 .name("move")
@@ -81,9 +347,9 @@ impl EventHandler for Handler {
                                 .create_sub_option(|option| {
                                     option
                                         .name("room")
-                                        .description("The room to move to.")
+                                        .description("The room to move to, or in dark caves the number of the tunnel to take.")
                                         .kind(CommandOptionType::Integer)
-                                        .max_int_value(19)
+                                        .max_int_value(wumpus::MAX_ROOMS - 1)
                                         .required(true)
                                 })
                         })
@@ -95,9 +361,9 @@ impl EventHandler for Handler {
                                 .create_sub_option(|option| {
                                     option
                                         .name("at_room")
-                                        .description("The room to shoot into.")
+                                        .description("The room to shoot into, or in dark caves the number of the tunnel to shoot down.")
                                         .kind(CommandOptionType::Integer)
-                                        .max_int_value(19)
+                                        .max_int_value(wumpus::MAX_ROOMS - 1)
                                         .required(true)
                                 })
                                 .create_sub_option(|option| {
@@ -106,9 +372,32 @@ impl EventHandler for Handler {
                                         .description("The number of rooms for the arrow to travel.")
                                         .kind(CommandOptionType::Integer)
                                         .max_int_value(5)
-                                        .required(true)
+                                })
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("path")
+                                        .description("Classic rules: up to four more rooms (tunnels, if dark) for the arrow to fly through, like 7 12.")
+                                        .kind(CommandOptionType::String)
+                                })
+                        })
+                        .create_option(|option| {
+                            option
+                                .name("stats")
+                                .description("Shows how a player's Hunt The Wumpus games have ended.")
+                                .kind(CommandOptionType::SubCommand)
+                                .create_sub_option(|option| {
+                                    option
+                                        .name("user")
+                                        .description("Defaults to you.")
+                                        .kind(CommandOptionType::User)
                                 })
                         })
+                        .create_option(|option| {
+                            option
+                                .name("leaderboard")
+                                .description("Shows who has killed the Wumpus most often in this server.")
+                                .kind(CommandOptionType::SubCommand)
+                        })
                 })
                 .create_application_command(|commands| {
                     commands
@@ -128,9 +417,10 @@ impl EventHandler for Handler {
                         .create_option(|option| {
                             option
                                 .name("roll")
-                                .description("roll definition")
+                                .description("Dice like 2d6+3, a saved macro, or save/share NAME EXPRESSION, forget NAME, macros.")
                                 .kind(CommandOptionType::String)
                                 .required(true)
+                                .set_autocomplete(true)
                         })
                 })
                 .create_application_command(|commands| {
@@ -165,39 +455,50 @@ impl EventHandler for Handler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(command) = interaction {
-            let result = match command.data.name.as_str() {
-                JEOPARDY_CMD => jeopardy(&ctx, command).await,
-                RAYZ_CMD => rayz(&ctx, command).await,
-                WUMPUS_CMD => htw(&ctx, command).await,
-                SPIRITS_CMD => spirits(&ctx, command).await,
-                ROLL_CMD => roll(&ctx, command).await,
-                DALLE_CMD => generate(&ctx, command).await,
-                _ => command
-                    .create_interaction_response(&ctx.http, |response| {
-                        response
-                            .kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|message| {
-                                message.content("not implemented :(")
-                            })
-                    })
-                    .await
-                    .map_err(Into::into),
-            };
-            if let Err(why) = result {
-                println!("Cannot respond to slash command: {}", why);
+        match interaction {
+            Interaction::ApplicationCommand(command) => application_command(ctx, command).await,
+            Interaction::MessageComponent(component) => {
+                let result = match command_namespace(&component.data.custom_id) {
+                    JEOPARDY_CMD => jeopardy::component(&ctx, component).await,
+                    WUMPUS_CMD => htw::component(&ctx, component).await,
+                    _ => Ok(()),
+                };
+                if let Err(why) = result {
+                    println!("Cannot respond to message component: {}", why);
+                }
+            }
+            Interaction::Autocomplete(autocomplete) => {
+                let result = match autocomplete.data.name.as_str() {
+                    ROLL_CMD => roll::autocomplete(&ctx, autocomplete).await,
+                    _ => Ok(()),
+                };
+                if let Err(why) = result {
+                    println!("Cannot respond to autocomplete: {}", why);
+                }
             }
+            Interaction::ModalSubmit(modal) => {
+                let result = match command_namespace(&modal.data.custom_id) {
+                    JEOPARDY_CMD => jeopardy::modal(&ctx, modal).await,
+                    _ => Ok(()),
+                };
+                if let Err(why) = result {
+                    println!("Cannot respond to modal submit: {}", why);
+                }
+            }
+            _ => {}
         }
     }
 
     async fn reaction_add(&self, ctx: Context, add_reaction: Reaction) {
-        if let Some(vote) = get_vote(add_reaction.emoji.as_data().as_str()) {
-            let mut db = get_data::<db_support::DB, _>(&ctx).await;
-            let discord_message_id = add_reaction.message_id.0;
-            let result = match vote {
-                Vote::Up => db.increment_jeopardy_category_post(discord_message_id),
-                Vote::Down => db.decrement_jeopardy_category_post(discord_message_id),
-            };
+        let vote = get_vote(add_reaction.emoji.as_data().as_str());
+        let db = get_data::<db_support::DB, _>(&ctx).await;
+        let user_id = voter(&ctx, add_reaction.user_id).await;
+        if let (Some(vote), Some(user_id), Some(mut db)) = (vote, user_id, db) {
+            let result = db.vote_jeopardy_category_post(db::models::NewJeopardyCategoryVote {
+                discord_message_id: add_reaction.message_id.0 as i64,
+                discord_user_id: user_id.0 as i64,
+                direction: vote.direction(),
+            });
             if let Err(err) = result {
                 eprintln!("{}", err);
             }
@@ -205,13 +506,15 @@ impl EventHandler for Handler {
     }
 
     async fn reaction_remove(&self, ctx: Context, removed_reaction: Reaction) {
-        if let Some(vote) = get_vote(removed_reaction.emoji.as_data().as_str()) {
-            let mut db = get_data::<db_support::DB, _>(&ctx).await;
-            let discord_message_id = removed_reaction.message_id.0;
-            let result = match vote {
-                Vote::Up => db.decrement_jeopardy_category_post(discord_message_id),
-                Vote::Down => db.increment_jeopardy_category_post(discord_message_id),
-            };
+        let vote = get_vote(removed_reaction.emoji.as_data().as_str());
+        let db = get_data::<db_support::DB, _>(&ctx).await;
+        let user_id = voter(&ctx, removed_reaction.user_id).await;
+        if let (Some(vote), Some(user_id), Some(mut db)) = (vote, user_id, db) {
+            let result = db.unvote_jeopardy_category_post(
+                removed_reaction.message_id.0,
+                user_id.0,
+                vote.direction(),
+            );
             if let Err(err) = result {
                 eprintln!("{}", err);
             }
@@ -219,6 +522,33 @@ impl EventHandler for Handler {
     }
 }
 
+async fn application_command(ctx: Context, command: ApplicationCommandInteraction) {
+    let result = match command.data.name.as_str() {
+        JEOPARDY_CMD => jeopardy(&ctx, command).await,
+        RAYZ_CMD => rayz(&ctx, command).await,
+        WUMPUS_CMD => htw::command(&ctx, command).await,
+        SPIRITS_CMD => spirits(&ctx, command).await,
+        ROLL_CMD => roll::command(&ctx, command).await,
+        DALLE_CMD => generate(&ctx, command).await,
+        _ => command
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| message.content("not implemented :("))
+            })
+            .await
+            .map_err(Into::into),
+    };
+    if let Err(why) = result {
+        println!("Cannot respond to slash command: {}", why);
+    }
+}
+
+/// Component and modal custom ids are prefixed with the name of the command that created them.
+fn command_namespace(custom_id: &str) -> &str {
+    custom_id.split(':').next().unwrap_or(custom_id)
+}
+
 async fn get_data<T, U>(ctx: &Context) -> <T as TypeMapKey>::Value
 where
     T: TypeMapKey<Value = U>,
@@ -231,6 +561,12 @@ where
         .clone()
 }
 
+/// Whoever reacted, unless it was the bot itself.
+async fn voter(ctx: &Context, user_id: Option<UserId>) -> Option<UserId> {
+    let bot = ctx.data.read().await.get::<BotUser>().copied();
+    user_id.filter(|&user_id| Some(user_id) != bot)
+}
+
 fn get_vote(reaction: &str) -> Option<jeopardy::Vote> {
     match reaction {
         "👍" => Some(jeopardy::Vote::Up),
@@ -264,18 +600,35 @@ async fn main() {
     let stabdiff_version = std::env::var("STABDIFF_VERSION").expect("need STABDIFF_VERSION");
     let stabdiff = stable_diffusion::Client::new(stabdiff_token, stabdiff_version);
 
-    let db = tokio::task::block_in_place(|| db::DB::new(db::DB::env_url().unwrap())).unwrap();
+    // without a database, Jeopardy! is played from the bundled archive and nothing is kept
+    let db = db::DB::env_url()
+        .ok()
+        .map(|url| tokio::task::block_in_place(|| db::DB::new(url)).unwrap());
+    let source: std::sync::Arc<dyn db::source::JeopardySource> = match &db {
+        Some(db) => std::sync::Arc::new(db.clone()),
+        None => {
+            eprintln!("No DATABASE_URL, so Jeopardy! will use the bundled archive.");
+            std::sync::Arc::new(::jeopardy::Jeopardy::new().expect("load JEOPARDY.csv"))
+        }
+    };
 
     let intents = GatewayIntents::non_privileged() | GatewayIntents::MESSAGE_CONTENT;
     let mut client = Client::builder(token, intents)
         .event_handler(Handler)
-        .type_map_insert::<HTWGamesTypeMap>(Default::default())
-        .type_map_insert::<db_support::DB>(db)
+        .type_map_insert::<htw::Games>(Default::default())
+        .type_map_insert::<jeopardy::Games>(Default::default())
+        .type_map_insert::<db_support::DB>(db.clone())
+        .type_map_insert::<db_support::Source>(source)
         .type_map_insert::<serenity_impls::StableDiffusionSupport>(stabdiff)
         .type_map_insert::<serenity_impls::DoSpacesSupport>(s3)
         .await
         .expect("Error creating client");
 
+    // the Clue of the Day needs somewhere to keep its subscriptions
+    if let Some(db) = db {
+        tokio::spawn(jeopardy::daily::run(client.cache_and_http.http.clone(), db));
+    }
+
     // start listening for events by starting a single shard
     if let Err(why) = client.start().await {
         println!("An error occurred while running the client: {:?}", why);
@@ -328,42 +681,6 @@ async fn generate(ctx: &Context, command: ApplicationCommandInteraction) -> eyre
     Ok(())
 }
 
-async fn roll(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
-    let input = command
-        .data
-        .options
-        .iter()
-        .find_map(|option| {
-            (option.name == "roll").then(|| option.value.as_ref().and_then(|value| value.as_str()))
-        })
-        .flatten()
-        .unwrap_or("");
-
-    let name = if let Some(guild) = command.guild_id {
-        command
-            .user
-            .nick_in(&ctx.http, guild)
-            .await
-            .unwrap_or(command.user.name.clone())
-    } else {
-        command.user.name.clone()
-    };
-    let content = match roll::roll(input) {
-        Ok(roll) => format!("{} rolled {}: {}", name, input, roll),
-        Err(err) => format!("{}", err),
-    };
-
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|message| message.content(content))
-        })
-        .await?;
-
-    Ok(())
-}
-
 async fn spirits(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
     let sub = command.data.options.get(0);
     let content: std::borrow::Cow<'static, str> = match sub.as_ref().map(|sub| sub.name.as_str()) {
@@ -381,118 +698,40 @@ async fn spirits(ctx: &Context, command: ApplicationCommandInteraction) -> eyre:
     Ok(())
 }
 
-type HTWGames = std::collections::HashMap<UserId, wumpus::HuntTheWumpus>;
-#[derive(Default)]
-struct HTWGamesTypeMap(std::sync::Arc<tokio::sync::Mutex<HTWGames>>);
-impl TypeMapKey for HTWGamesTypeMap {
-    type Value = Self;
-}
-
-async fn htw(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
-    use std::collections::hash_map::Entry;
-
-    let data = ctx.data.read().await;
-    let games_ty = data.get::<HTWGamesTypeMap>().unwrap();
-    let mut games = games_ty.0.lock().await;
-    let maybe_game = games.entry(command.user.id);
-
-    let cmd = &command.data.options[0];
-    let content: std::borrow::Cow<'static, str> = match cmd.name.as_str() {
-        "start" => match maybe_game {
-            Entry::Occupied(entry) => format!(
-                "There is already a game running for you. \n{}",
-                entry.get().status()
-            )
-            .into(),
-            Entry::Vacant(entry) => {
-                let game = wumpus::HuntTheWumpus::new();
-                let status = game.status();
-                entry.insert(game);
-                status.into()
-            }
-        },
-        name => match maybe_game {
-            Entry::Occupied(mut game) => {
-                let content = match name {
-                    "status" => game.get().status().into(),
-                    "move" => {
-                        let new_room =
-                            cmd.options[0].value.as_ref().unwrap().as_u64().unwrap() as usize;
-                        if game.get_mut().move_player_to(new_room) {
-                            game.get().status().into()
-                        } else {
-                            format!(
-                                "That's not a room you can move to!\n{}",
-                                game.get().status()
-                            )
-                            .into()
-                        }
-                    }
-                    "shoot" => {
-                        fn get_usize(
-                            name: &'static str,
-                        ) -> impl FnMut(&CommandDataOption) -> Option<usize>
-                        {
-                            move |option| {
-                                (option.name == name).then(|| {
-                                    option.value.as_ref().unwrap().as_u64().unwrap() as usize
-                                })
-                            }
-                        }
-
-                        let at_room = cmd.options.iter().find_map(get_usize("at_room")).unwrap();
-                        let room_travel = cmd
-                            .options
-                            .iter()
-                            .find_map(get_usize("room_travel"))
-                            .unwrap();
-                        if game.get_mut().shoot(at_room, room_travel) {
-                            game.get().status().into()
-                        } else {
-                            format!(
-                                "That's not a room you can shoot into!\n{}",
-                                game.get().status()
-                            )
-                            .into()
-                        }
-                    }
-                    _ => "Unrecognized subcommand".into(),
-                };
-                if !game.get().is_running() {
-                    game.remove_entry();
-                }
-                content
-            }
-            Entry::Vacant(_) => "There's no game running for you.".into(),
-        },
-    };
-
-    command
-        .create_interaction_response(&ctx.http, |response| {
-            response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|message| message.content(content))
-        })
-        .await?;
-
-    Ok(())
+async fn jeopardy(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let sub = command.data.options.first();
+    match sub.map(|sub| sub.name.as_str()) {
+        Some("play") => jeopardy::play(ctx, command).await,
+        Some("board") => jeopardy::board(ctx, command).await,
+        Some("pick") => jeopardy::pick(ctx, command).await,
+        Some("leaderboard") => jeopardy::leaderboard(ctx, command).await,
+        Some("stats") => jeopardy::stats(ctx, command).await,
+        Some("search") => jeopardy::search(ctx, command).await,
+        Some("history") => jeopardy::history(ctx, command).await,
+        Some("daily") => jeopardy::daily::subscribe(ctx, command).await,
+        Some("export") => jeopardy::export(ctx, command).await,
+        _ => jeopardy_random(ctx, command).await,
+    }
 }
 
-async fn jeopardy(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+async fn jeopardy_random(
+    ctx: &Context,
+    command: ApplicationCommandInteraction,
+) -> eyre::Result<()> {
+    let selection = command
+        .data
+        .options
+        .first()
+        .and_then(|sub| sub.options.iter().find(|option| option.name == "selection"))
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(jeopardy::Selection::Random);
+    let filter = jeopardy::category_filter(&command);
+    let guild_id = command.guild_id;
     let fetch = {
-        let mut db = get_data::<db_support::DB, _>(ctx).await;
-        move || -> eyre::Result<_> {
-            // 100 is a guard against an infinite loop
-            (0..100)
-                .find_map(|_| {
-                    let result = db.random_jeopardy_category();
-                    match result {
-                        Ok((c, q)) => (q.len() == 5).then(|| Ok((c, q))),
-                        Err(err) => Some(Err(err)),
-                    }
-                })
-                .unwrap()
-        }
+        let source = get_data::<db_support::Source, _>(ctx).await;
+        move || jeopardy::random_category(source.as_ref(), selection, guild_id, filter)
     };
     let result = tokio::task::block_in_place(fetch);
 
@@ -508,11 +747,11 @@ async fn jeopardy(ctx: &Context, command: ApplicationCommandInteraction) -> eyre
                 .interaction_response_data(|message| message.content(content))
         })
         .await?;
-    if let Some(category) = category {
+    let db = get_data::<db_support::DB, _>(ctx).await;
+    if let (Some(category), Some(mut db)) = (category, db) {
         let msg = command.get_interaction_response(&ctx.http).await?;
-        tokio::task::block_in_place({
-            let mut db = get_data::<db_support::DB, _>(ctx).await;
-            move || db.record_jeopardy_category_post(category.id, msg.id.0)
+        tokio::task::block_in_place(move || {
+            db.record_jeopardy_category_post(category.id, msg.id.0, guild_id.map(|id| id.0))
         })?;
     }
 
@@ -1,7 +1,609 @@
+//! `/roll`, which rolls dice with [`caith`], and the macros players save for the rolls they make
+//! often. `/roll save attack 1d20+$1` saves a macro for yourself and `/roll share` saves one for
+//! everyone in the server, which `/roll attack 5` then rolls with `$1` replaced by 5. A shared
+//! macro can only be replaced or unshared by whoever shared it or by the server's managers.
+
 use caith::*;
+use serenity::builder::CreateEmbed;
+use serenity::model::application::interaction::{
+    application_command::ApplicationCommandInteraction, autocomplete::AutocompleteInteraction,
+    InteractionResponseType,
+};
+use serenity::prelude::*;
+use serenity::utils::Colour;
+
+/// The most macros a user or a server can save, which is also as many as autocomplete can show.
+const MAX_MACROS: usize = 25;
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Words that `/roll` takes as commands, so they can't be macro names.
+const SAVE: &str = "save";
+const SHARE: &str = "share";
+const FORGET: &str = "forget";
+const MACROS: &str = "macros";
+
+/// Repeated rolls get a field each, up to as many as an embed can have alongside the total.
+const MAX_ROLL_FIELDS: usize = 24;
+/// The most an embed field can hold.
+const MAX_FIELD_LENGTH: usize = 1024;
+/// The most an embed can hold across its title, description, fields and footer.
+const MAX_EMBED_LENGTH: usize = 6000;
+/// Kept back from the rolls for the total and the footer that follow them.
+const RESERVED_LENGTH: usize = 100;
+
+/// A roll, with every die caith rolled for it, including the ones it didn't count.
+pub struct Rolled {
+    result: RollResult,
+    dice: Vec<u64>,
+}
 
-pub fn roll(input: &str) -> eyre::Result<String> {
+/// Rolls dice for caith, and remembers them.
+struct Recorder(Vec<u64>);
+
+impl DiceRollSource for Recorder {
+    fn roll_single_die(&mut self, sides: u64) -> u64 {
+        let die = rand::Rng::gen_range(&mut rand::thread_rng(), 1..=sides);
+        self.0.push(die);
+        die
+    }
+}
+
+pub fn roll(input: &str) -> eyre::Result<Rolled> {
     let roller = Roller::new(input)?;
-    let result = roller.roll()?;
-    Ok(result.to_string())
+    let mut recorder = Recorder(vec![]);
+    let result = roller.roll_with(&mut recorder)?;
+    Ok(Rolled {
+        result,
+        dice: recorder.0,
+    })
+}
+
+impl Rolled {
+    /// The roll die by die. Highest rolls are in bold, lowest rolls are underlined, and dice
+    /// that don't count are struck out.
+    pub fn embed(&self, title: &str) -> CreateEmbed {
+        let rolls = match self.result.get_result() {
+            RollResultType::Single(roll) => vec![roll],
+            RollResultType::Repeated(repeated) => repeated.get_rolls().iter().collect(),
+        };
+        let dropped = dropped(&rolls, &self.dice);
+        let dropped = |i: usize| dropped.as_ref().map_or(&[][..], |dropped| &dropped[i][..]);
+
+        let mut embed = CreateEmbed::default();
+        let title = title.chars().take(256).collect::<String>();
+        let mut length = title.chars().count();
+        embed.title(title);
+        if let Some(reason) = self.result.get_reason() {
+            let reason = reason.chars().take(MAX_FIELD_LENGTH).collect::<String>();
+            length += reason.chars().count();
+            embed.description(reason);
+        }
+        match self.result.get_result() {
+            RollResultType::Single(roll) => {
+                embed
+                    .field("Dice", breakdown(roll, dropped(0)), false)
+                    .field("Total", roll.get_total(), true);
+            }
+            RollResultType::Repeated(repeated) => {
+                let mut shown = 0;
+                for (i, roll) in rolls.iter().enumerate().take(MAX_ROLL_FIELDS) {
+                    let name = format!("#{}", i + 1);
+                    let value =
+                        format!("{} = **{}**", breakdown(roll, dropped(i)), roll.get_total());
+                    length += name.chars().count() + value.chars().count();
+                    if length > MAX_EMBED_LENGTH - RESERVED_LENGTH {
+                        break;
+                    }
+                    embed.field(name, value, true);
+                    shown += 1;
+                }
+                if let Some(total) = repeated.get_total() {
+                    embed.field("Total", total, false);
+                }
+                if rolls.len() > shown {
+                    embed.footer(|footer| {
+                        footer.text(format!("{} more rolls aren't shown.", rolls.len() - shown))
+                    });
+                }
+            }
+        }
+
+        let dice = rolls
+            .iter()
+            .flat_map(|roll| roll.get_history())
+            .flat_map(|step| match step {
+                RollHistory::Roll(dice) => &dice[..],
+                _ => &[],
+            })
+            .collect::<Vec<_>>();
+        let highest = dice.iter().any(|die| matches!(die.crit, Critic::Max));
+        let lowest = dice.iter().any(|die| matches!(die.crit, Critic::Min));
+        match (highest, lowest) {
+            (true, false) => embed.colour(Colour::DARK_GREEN),
+            (false, true) => embed.colour(Colour::RED),
+            _ => &mut embed,
+        };
+        embed
+    }
+}
+
+/// The dice rolled for each of `rolls` that don't count towards its total, because they were
+/// dropped or rerolled. caith only keeps the dice that count, so the rest are what's left of
+/// everything it rolled. Repeated rolls take turns rolling, so they're told apart by assuming
+/// they each rolled as many dice, and when they didn't, nothing is shown as dropped.
+fn dropped(rolls: &[&SingleRollResult], dice: &[u64]) -> Option<Vec<Vec<u64>>> {
+    let fudged = rolls
+        .iter()
+        .flat_map(|roll| roll.get_history())
+        .any(|step| matches!(step, RollHistory::Fudge(_)));
+    if fudged || rolls.is_empty() || !dice.len().is_multiple_of(rolls.len()) {
+        return None;
+    }
+    let per_roll = dice.len() / rolls.len();
+    rolls
+        .iter()
+        .enumerate()
+        .map(|(i, roll)| {
+            let mut left = dice[i * per_roll..(i + 1) * per_roll].to_vec();
+            for step in roll.get_history() {
+                if let RollHistory::Roll(kept) = step {
+                    for die in kept {
+                        let i = left.iter().position(|&rolled| rolled == die.res)?;
+                        left.remove(i);
+                    }
+                }
+            }
+            Some(left)
+        })
+        .collect()
+}
+
+/// A roll as caith worked it out, with each die shown and the dice that were dropped at the end.
+fn breakdown(roll: &SingleRollResult, dropped: &[u64]) -> String {
+    let mut text = String::new();
+    for step in roll.get_history() {
+        match step {
+            RollHistory::Roll(dice) => {
+                let dice = dice
+                    .iter()
+                    .map(|die| match die.crit {
+                        Critic::Max => format!("**{}**", die.res),
+                        Critic::Min => format!("__{}__", die.res),
+                        Critic::No => die.res.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                text.push_str(&format!("[{}]", dice.join(", ")));
+            }
+            RollHistory::Fudge(dice) => {
+                let dice = dice.iter().map(u64::to_string).collect::<Vec<_>>();
+                text.push_str(&format!("[{}]", dice.join(", ")));
+            }
+            RollHistory::Value(value) => text.push_str(&value.to_string()),
+            RollHistory::Separator(separator) => text.push_str(separator),
+            RollHistory::OpenParenthesis => text.push('('),
+            RollHistory::CloseParenthesis => text.push(')'),
+        }
+    }
+    if !dropped.is_empty() {
+        let dropped = dropped.iter().map(u64::to_string).collect::<Vec<_>>();
+        text.push_str(&format!(" · dropped ~~{}~~", dropped.join(", ")));
+    }
+    // leave room for the total after repeated rolls
+    if text.chars().count() > MAX_FIELD_LENGTH - 32 {
+        text = text.chars().take(MAX_FIELD_LENGTH - 33).collect::<String>() + "…";
+    }
+    text
+}
+
+/// Checks that `name` can be given to a macro, and returns it as it's saved, in lowercase.
+/// Names that caith could roll by themselves, like `d20`, aren't allowed either.
+fn macro_name(name: &str) -> Result<String, String> {
+    let name = name.to_lowercase();
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && name.len() <= MAX_NAME_LENGTH;
+    if !valid {
+        Err(format!(
+            "Macro names start with a letter and have up to {} letters, numbers, - or _.",
+            MAX_NAME_LENGTH
+        ))
+    } else if [SAVE, SHARE, FORGET, MACROS].contains(&name.as_str()) {
+        Err(format!("{} can't be a macro name.", name))
+    } else if Roller::new(&name).is_ok() {
+        Err(format!("{} is already a dice expression.", name))
+    } else {
+        Ok(name)
+    }
+}
+
+/// How many arguments a macro takes: as many as the highest `$1` to `$9` in it.
+fn arity(expression: &str) -> usize {
+    let mut chars = expression.chars().peekable();
+    let mut arity = 0;
+    while let Some(c) = chars.next() {
+        if let ('$', Some(digit)) = (c, chars.peek().and_then(|c| c.to_digit(10))) {
+            arity = arity.max(digit as usize);
+        }
+    }
+    arity
+}
+
+/// Replaces `$1` to `$9` in a macro's expression with its arguments.
+fn expand(expression: &str, args: &[&str]) -> Result<String, String> {
+    let arity = arity(expression);
+    if args.len() != arity {
+        return Err(match arity {
+            0 => "This macro doesn't take any arguments.".to_owned(),
+            1 => "This macro takes 1 argument.".to_owned(),
+            _ => format!("This macro takes {} arguments.", arity),
+        });
+    }
+    let mut expanded = String::with_capacity(expression.len());
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().and_then(|c| c.to_digit(10))) {
+            ('$', Some(digit)) if digit > 0 => {
+                chars.next();
+                expanded.push_str(args[digit as usize - 1]);
+            }
+            _ => expanded.push(c),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Checks that a macro's expression can be rolled, with every argument standing in as a 1.
+fn check_expression(expression: &str) -> Result<(), String> {
+    let ones = vec!["1"; arity(expression)];
+    let expanded = expand(expression, &ones)?;
+    match Roller::new(&expanded) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("{} can't be rolled: {}", expression, err)),
+    }
+}
+
+/// Handles `/roll`. Its one option is a dice expression, a saved macro and its arguments, or
+/// one of the commands that manage macros.
+pub async fn command(ctx: &Context, command: ApplicationCommandInteraction) -> eyre::Result<()> {
+    let input = command
+        .data
+        .options
+        .iter()
+        .find_map(|option| {
+            (option.name == "roll").then(|| option.value.as_ref().and_then(|value| value.as_str()))
+        })
+        .flatten()
+        .unwrap_or("")
+        .trim();
+    let mut words = input.split_whitespace();
+    let first = words.next().unwrap_or_default();
+    let rest = words.collect::<Vec<_>>();
+
+    let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+    let user_id = command.user.id.0;
+    let guild_id = command.guild_id.map(|guild_id| guild_id.0);
+    let manager = command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild());
+    let mut embed = None;
+    let (content, ephemeral) = match first.to_lowercase().as_str() {
+        SAVE | SHARE => {
+            let shared = first.eq_ignore_ascii_case(SHARE);
+            let owner = if shared { guild_id } else { Some(user_id) };
+            match (owner, rest.as_slice()) {
+                (None, _) => ("Macros can only be shared in servers.".to_owned(), true),
+                (Some(owner), [name, expression @ ..]) if !expression.is_empty() => {
+                    let expression = expression.join(" ");
+                    let result = macro_name(name)
+                        .and_then(|name| check_expression(&expression).map(|()| name))
+                        .map_err(|err| eyre::eyre!(err))
+                        .and_then(|name| {
+                            tokio::task::block_in_place(|| {
+                                save(db, owner, &name, &expression, user_id, manager).map(|()| name)
+                            })
+                        });
+                    match result {
+                        Ok(name) if shared => (
+                            format!(
+                                "{} shared **{}**: `{}`. Roll it with /roll {}.",
+                                command.user.mention(),
+                                name,
+                                expression,
+                                name
+                            ),
+                            false,
+                        ),
+                        Ok(name) => (
+                            format!(
+                                "Saved **{}**: `{}`. Roll it with /roll {}.",
+                                name, expression, name
+                            ),
+                            true,
+                        ),
+                        Err(err) => (err.to_string(), true),
+                    }
+                }
+                _ => (
+                    format!("Usage: {} NAME EXPRESSION", first.to_lowercase()),
+                    true,
+                ),
+            }
+        }
+        FORGET => {
+            let name = rest.first().copied().unwrap_or_default().to_lowercase();
+            let result = tokio::task::block_in_place(|| -> eyre::Result<_> {
+                let mut db = crate::db_support::required(db)?;
+                for owner in std::iter::once(user_id).chain(guild_id) {
+                    let macros = db.roll_macros(&[owner])?;
+                    if let Some(existing) = macros.iter().find(|m| m.name == name) {
+                        check_in_charge(existing, user_id, manager)?;
+                        db.delete_roll_macro(owner, &name)?;
+                        return Ok(Some(owner));
+                    }
+                }
+                Ok(None)
+            });
+            match result {
+                Ok(Some(owner)) if owner == user_id => (format!("Forgot **{}**.", name), true),
+                Ok(Some(_)) => (
+                    format!(
+                        "{} unshared **{}** from this server.",
+                        command.user.mention(),
+                        name
+                    ),
+                    false,
+                ),
+                Ok(None) => (format!("There's no macro called {}.", name), true),
+                Err(err) => (err.to_string(), true),
+            }
+        }
+        MACROS => {
+            let result = tokio::task::block_in_place(|| {
+                let owners = std::iter::once(user_id).chain(guild_id).collect::<Vec<_>>();
+                crate::db_support::required(db)?.roll_macros(&owners)
+            });
+            let content = match result {
+                Ok(macros) if macros.is_empty() => {
+                    "There are no macros yet. Save one with /roll save NAME EXPRESSION.".to_owned()
+                }
+                Ok(macros) => {
+                    let list = |owner: u64| {
+                        macros
+                            .iter()
+                            .filter(|m| m.discord_id == owner as i64)
+                            .map(|m| format!("**{}**: `{}`", m.name, m.expression))
+                            .collect::<Vec<_>>()
+                    };
+                    let mut sections = vec![];
+                    for (title, owner) in std::iter::once(("Yours", user_id))
+                        .chain(guild_id.map(|guild_id| ("This server's", guild_id)))
+                    {
+                        let lines = list(owner);
+                        if !lines.is_empty() {
+                            sections.push(format!("{}:\n{}", title, lines.join("\n")));
+                        }
+                    }
+                    sections.join("\n\n")
+                }
+                Err(err) => err.to_string(),
+            };
+            (content, true)
+        }
+        _ => {
+            let name = if let Some(guild) = command.guild_id {
+                command
+                    .user
+                    .nick_in(&ctx.http, guild)
+                    .await
+                    .unwrap_or(command.user.name.clone())
+            } else {
+                command.user.name.clone()
+            };
+            let saved = match (db, macro_name(first)) {
+                (Some(mut db), Ok(macro_name)) => {
+                    tokio::task::block_in_place(|| find(&mut db, user_id, guild_id, &macro_name))
+                }
+                _ => Ok(None),
+            };
+            let expanded = match saved {
+                Ok(Some(expression)) => expand(&expression, &rest).map(Some),
+                Ok(None) => Ok(None),
+                Err(err) => Err(err.to_string()),
+            };
+            match expanded {
+                Ok(expanded) => match roll(expanded.as_deref().unwrap_or(input)) {
+                    Ok(rolled) => {
+                        let title = match expanded {
+                            Some(expanded) => format!("{} rolled {} ({})", name, input, expanded),
+                            None => format!("{} rolled {}", name, input),
+                        };
+                        embed = Some(rolled.embed(&title));
+                        (String::new(), false)
+                    }
+                    Err(err) => (format!("{}", err), false),
+                },
+                Err(err) => (err, true),
+            }
+        }
+    };
+
+    command
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|message| {
+                    message
+                        .content(content)
+                        .set_embeds(embed)
+                        .ephemeral(ephemeral)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Suggests the saved macros whose names start with what's been typed so far.
+pub async fn autocomplete(
+    ctx: &Context,
+    autocomplete: AutocompleteInteraction,
+) -> eyre::Result<()> {
+    let typed = autocomplete
+        .data
+        .options
+        .iter()
+        .find(|option| option.focused)
+        .and_then(|option| option.value.as_ref())
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .trim_start()
+        .to_lowercase();
+    let db = crate::get_data::<crate::db_support::DB, _>(ctx).await;
+    // once there are arguments there's nothing left to complete
+    let macros = match db {
+        Some(mut db) if !typed.contains(char::is_whitespace) => {
+            let owners = std::iter::once(autocomplete.user.id.0)
+                .chain(autocomplete.guild_id.map(|guild_id| guild_id.0))
+                .collect::<Vec<_>>();
+            tokio::task::block_in_place(|| db.roll_macros(&owners))?
+        }
+        _ => vec![],
+    };
+
+    let mut names = std::collections::HashSet::new();
+    autocomplete
+        .create_autocomplete_response(&ctx.http, |response| {
+            // yours come first, and hide the server's of the same name
+            let owner = autocomplete.user.id.0 as i64;
+            let mut macros = macros
+                .iter()
+                .filter(|m| m.name.starts_with(&typed))
+                .collect::<Vec<_>>();
+            macros.sort_by_key(|m| m.discord_id != owner);
+            for m in macros {
+                if names.len() < MAX_MACROS && names.insert(&m.name) {
+                    let label = format!("{}: {}", m.name, m.expression);
+                    response
+                        .add_string_choice(label.chars().take(100).collect::<String>(), &m.name);
+                }
+            }
+            response
+        })
+        .await?;
+    Ok(())
+}
+
+/// Saves a macro for `owner` on behalf of `user_id`, as long as there aren't too many already and
+/// it doesn't replace a shared macro that someone else is in charge of.
+fn save(
+    db: Option<db::DB>,
+    owner: u64,
+    name: &str,
+    expression: &str,
+    user_id: u64,
+    manager: bool,
+) -> eyre::Result<()> {
+    let mut db = crate::db_support::required(db)?;
+    let macros = db.roll_macros(&[owner])?;
+    match macros.iter().find(|m| m.name == name) {
+        Some(existing) => check_in_charge(existing, user_id, manager)?,
+        None if macros.len() >= MAX_MACROS => eyre::bail!(
+            "There are already {} macros here. Forget one with /roll forget NAME first.",
+            MAX_MACROS
+        ),
+        None => {}
+    }
+    db.save_roll_macro(owner, name, expression, user_id)
+}
+
+/// Only whoever shared a macro, or someone who can manage the server, can replace or unshare it.
+fn check_in_charge(
+    existing: &db::models::RollMacro,
+    user_id: u64,
+    manager: bool,
+) -> eyre::Result<()> {
+    let personal = existing.discord_id == user_id as i64;
+    if personal || manager || existing.created_by == user_id as i64 {
+        Ok(())
+    } else {
+        eyre::bail!(
+            "**{}** was shared by someone else. Only they or the server's managers can change it.",
+            existing.name
+        )
+    }
+}
+
+/// The expression saved as `name`, by the user or else by their server.
+fn find(
+    db: &mut db::DB,
+    user_id: u64,
+    guild_id: Option<u64>,
+    name: &str,
+) -> eyre::Result<Option<String>> {
+    let owners = std::iter::once(user_id).chain(guild_id).collect::<Vec<_>>();
+    let mut macros = db.roll_macros(&owners)?;
+    macros.retain(|m| m.name == name);
+    macros.sort_by_key(|m| m.discord_id != user_id as i64);
+    Ok(macros.into_iter().next().map(|m| m.expression))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn macro_names() {
+        assert_eq!(macro_name("Attack"), Ok("attack".to_owned()));
+        assert_eq!(
+            macro_name("sneak-attack_2"),
+            Ok("sneak-attack_2".to_owned())
+        );
+        assert!(macro_name("2attack").is_err());
+        assert!(macro_name("at tack").is_err());
+        assert!(macro_name("save").is_err());
+        assert!(macro_name("d20").is_err());
+    }
+
+    #[test]
+    fn expanding_arguments() {
+        assert_eq!(arity("1d20+5"), 0);
+        assert_eq!(arity("$2d6+$1"), 2);
+        assert_eq!(expand("1d20+5", &[]), Ok("1d20+5".to_owned()));
+        assert_eq!(expand("$2d6+$1", &["3", "4"]), Ok("4d6+3".to_owned()));
+        assert_eq!(expand("1d20+$1+$1", &["2"]), Ok("1d20+2+2".to_owned()));
+        assert!(expand("1d20+$1", &[]).is_err());
+        assert!(expand("1d20", &["2"]).is_err());
+    }
+
+    #[test]
+    fn dropped_dice() {
+        let rolled = roll("4d6k3").unwrap();
+        let single = rolled.result.as_single().unwrap();
+        assert_eq!(rolled.dice.len(), 4);
+        assert_eq!(dropped(&[single], &rolled.dice).unwrap()[0].len(), 1);
+
+        let rolled = roll("2d6^3").unwrap();
+        let repeated = rolled.result.as_repeated().unwrap();
+        let rolls = repeated.get_rolls().iter().collect::<Vec<_>>();
+        assert_eq!(dropped(&rolls, &rolled.dice), Some(vec![vec![]; 3]));
+    }
+
+    #[test]
+    fn embeds_fit() {
+        let embed = roll("200d6^20").unwrap().embed("200d6^20");
+        let text =
+            |value: &serde_json::Value| value.as_str().map_or(0, |text| text.chars().count());
+        let fields = embed.0["fields"].as_array().unwrap();
+        let length = text(&embed.0["title"])
+            + text(&embed.0["footer"]["text"])
+            + fields
+                .iter()
+                .map(|field| text(&field["name"]) + text(&field["value"]))
+                .sum::<usize>();
+        assert!(length <= MAX_EMBED_LENGTH, "{} characters", length);
+        assert!(fields.len() < 21);
+    }
 }
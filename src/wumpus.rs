@@ -1,7 +1,196 @@
 use rand::seq::{IteratorRandom, SliceRandom};
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Copy, Clone)]
+mod cave;
+
+pub use cave::{Cave, CaveError, Layout, MAX_ROOMS, MIN_ROOMS, TORUS_ROWS};
+
+/// The most rooms an arrow can fly through.
+pub const MAX_ARROW_PATH: usize = 5;
+/// Arrows each player starts with.
+const ARROWS: usize = 5;
+pub const MAX_PLAYERS: usize = 6;
+
+/// The dice games roll unless they're given others. Unlike `thread_rng`, they can be seeded,
+/// and saved along with the game so that it rolls on where it left off.
+pub type Dice = rand_chacha::ChaCha8Rng;
+
+/// Dice that can be saved along with a game.
+pub trait SavedDice: Sized {
+    fn save(&self) -> DiceState;
+    fn load(state: DiceState) -> Result<Self, String>;
+}
+
+/// Where a game's dice are up to. ChaCha's own serialization keeps its word position as a
+/// `u128`, which `serde_json::Value` can't hold, so it's saved as a string instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiceState {
+    seed: [u8; 32],
+    stream: u64,
+    word_pos: String,
+}
+
+impl SavedDice for Dice {
+    fn save(&self) -> DiceState {
+        DiceState {
+            seed: self.get_seed(),
+            stream: self.get_stream(),
+            word_pos: self.get_word_pos().to_string(),
+        }
+    }
+
+    fn load(state: DiceState) -> Result<Self, String> {
+        let word_pos = state
+            .word_pos
+            .parse::<u128>()
+            .map_err(|err| format!("Bad word position {:?}: {}", state.word_pos, err))?;
+        let mut dice = Self::from_seed(state.seed);
+        dice.set_stream(state.stream);
+        dice.set_word_pos(word_pos);
+        Ok(dice)
+    }
+}
+
+/// Which version of the game is being played.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rules {
+    /// The original 1973 rules. Arrows fly through a path of up to five rooms, and the Wumpus
+    /// wakes when an arrow misses or when it's bumped into, and usually moves.
+    Classic,
+    /// Arrows fly a number of rooms through random tunnels, and the Wumpus never moves.
+    #[default]
+    Current,
+}
+
+impl std::str::FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(Rules::Classic),
+            "current" => Ok(Rules::Current),
+            _ => Err(format!("Unknown rules: {}", s)),
+        }
+    }
+}
+
+/// Who wins when the Wumpus is killed. It only matters when there's more than one player.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    /// Everyone hunts together, and wins together.
+    #[default]
+    Coop,
+    /// Only whoever shoots the Wumpus wins.
+    Versus,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "coop" => Ok(Mode::Coop),
+            "versus" => Ok(Mode::Versus),
+            _ => Err(format!("Unknown mode: {}", s)),
+        }
+    }
+}
+
+/// What happened on the last turn, besides where it left the player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Event {
+    BumpedWumpus,
+    BatSnatch,
+    Missed,
+    /// A player's game ended while there are others to announce it to.
+    Died {
+        player: u64,
+        how: State,
+    },
+}
+
+/// A turn that changed a game, as kept in its log. Playing a game's log again from the same seed
+/// plays the same game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Turn {
+    Join(u64),
+    Leave(u64),
+    Move(usize),
+    Shoot { at_room: usize, room_travel: usize },
+    ShootPath(Vec<usize>),
+}
+
+/// Why a log can't be replayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Cave(CaveError),
+    /// The turn at this index in the log couldn't be played.
+    Illegal(usize),
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Cave(err) => err.fmt(f),
+            ReplayError::Illegal(index) => write!(f, "Turn {} of the log can't be played.", index),
+        }
+    }
+}
+
+/// Why a player can't join a game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    AlreadyPlaying,
+    Full,
+    Over,
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JoinError::AlreadyPlaying => write!(f, "You're already in this hunt."),
+            JoinError::Full => write!(f, "This hunt already has {} players.", MAX_PLAYERS),
+            JoinError::Over => write!(f, "This hunt is over."),
+        }
+    }
+}
+
+/// Why an arrow's path can't be shot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathError {
+    Length,
+    NoSuchRoom(usize),
+    /// The arrow would have to double straight back, like 3 then 7 then 3.
+    TooCrooked(usize),
+    /// A dark cave's room has no tunnel with this number.
+    NoSuchTunnel(usize),
+    /// Like `TooCrooked`, for a path of tunnel numbers through a dark cave.
+    TooCrookedTunnel(usize),
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::Length => write!(f, "An arrow flies through 1 to {} rooms.", MAX_ARROW_PATH),
+            PathError::NoSuchRoom(room) => write!(f, "There's no room {}.", room),
+            PathError::TooCrooked(room) => write!(
+                f,
+                "Arrows aren't that crooked - try another room than {}.",
+                room
+            ),
+            PathError::NoSuchTunnel(tunnel) => write!(f, "There's no tunnel {}.", tunnel),
+            PathError::TooCrookedTunnel(tunnel) => write!(
+                f,
+                "Arrows aren't that crooked - try another tunnel than {}.",
+                tunnel
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 enum State {
     Running,
     Won,
@@ -11,92 +200,540 @@ enum State {
     GameOverArrows,
 }
 
-const ROOMS: [[usize; 3]; 20] = [
-    [1, 4, 7],
-    [0, 2, 9],
-    [1, 3, 11],
-    [2, 4, 13],
-    [0, 3, 5],
-    [4, 6, 14],
-    [5, 7, 16],
-    [0, 6, 8],
-    [7, 9, 17],
-    [1, 8, 10],
-    [9, 11, 18],
-    [2, 10, 12],
-    [11, 13, 19],
-    [3, 12, 14],
-    [5, 13, 15],
-    [14, 16, 19],
-    [6, 15, 17],
-    [8, 16, 18],
-    [10, 17, 19],
-    [12, 15, 18],
-];
-
-#[derive(Debug, Copy, Clone)]
-pub struct HuntTheWumpus {
-    player: Player,
-    rooms: [Room; 20],
+impl State {
+    fn name(self) -> &'static str {
+        match self {
+            State::Running => "Running",
+            State::Won => "Won",
+            State::GameOverShot => "GameOverShot",
+            State::GameOverWumpus => "GameOverWumpus",
+            State::GameOverPit => "GameOverPit",
+            State::GameOverArrows => "GameOverArrows",
+        }
+    }
+}
+
+/// How a finished game went for one player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub player: u64,
+    /// The name of the state the game ended in for them, like `Won` or `GameOverPit`, `Lost` if
+    /// someone else won a versus game, or `Abandoned` if they left before it was over.
+    pub result: &'static str,
+    pub moves: usize,
+    pub arrows_used: usize,
+}
+
+/// What a player noticed in a room, the last time they were there.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Senses {
+    pub wumpus: bool,
+    pub bats: bool,
+    pub draft: bool,
+}
+
+/// Where the hazards are.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Hazards {
+    pub wumpus: usize,
+    pub pits: [usize; 2],
+    pub bats: [usize; 2],
+}
+
+/// What a player knows about a cave, for drawing a map of it.
+#[derive(Debug, Clone)]
+pub struct Map<'a> {
+    pub cave: &'a Cave,
+    pub layout: Layout,
+    pub dark: bool,
+    /// The player's room.
+    pub room: usize,
+    /// The rooms the player has been in.
+    pub seen: &'a BTreeMap<usize, Senses>,
+    /// The rooms everyone else still hunting is in.
+    pub hunters: Vec<usize>,
+    /// Nobody knows where the hazards are until the game is over.
+    pub hazards: Option<Hazards>,
+}
+
+/// How a new game is set up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub rules: Rules,
+    pub layout: Layout,
+    /// How many rooms the cave has. Dodecahedrons always have 20.
+    pub rooms: usize,
+    /// Dark caves don't show where tunnels lead.
+    pub dark: bool,
+    pub mode: Mode,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rules: Rules::default(),
+            layout: Layout::default(),
+            rooms: 20,
+            dark: false,
+            mode: Mode::default(),
+        }
+    }
+}
+
+/// A game of Hunt the Wumpus for one or more players, who take turns. Players are known by
+/// their Discord ids, and are mentioned by them when there's more than one. Everything left to
+/// chance is rolled with `R`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    remote = "Self",
+    bound(serialize = "R: SavedDice", deserialize = "R: SavedDice + SeedableRng")
+)]
+pub struct HuntTheWumpus<R = Dice> {
+    #[serde(alias = "player", deserialize_with = "one_or_many")]
+    players: Vec<Player>,
+    rooms: Cave,
     wumpus: Wumpus,
     pits: [usize; 2],
     bats: [usize; 2],
-    arrows: usize,
     state: State,
+    #[serde(default)]
+    rules: Rules,
+    #[serde(default)]
+    events: Vec<Event>,
+    #[serde(default)]
+    layout: Layout,
+    #[serde(default)]
+    dark: bool,
+    #[serde(default)]
+    mode: Mode,
+    /// The index of the player whose turn it is.
+    #[serde(default)]
+    turn: usize,
+    #[serde(default)]
+    winner: Option<u64>,
+    /// Games saved before they kept their dice get new ones.
+    #[serde(
+        default = "unseeded",
+        serialize_with = "save_dice",
+        deserialize_with = "load_dice"
+    )]
+    rng: R,
+    /// What the dice were seeded with, if it's known.
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    log: Vec<Turn>,
+    /// Games saved before there could be more than one player kept the player's arrows here.
+    /// They're handed to the player as the game is loaded.
+    #[serde(default, rename = "arrows", skip_serializing)]
+    legacy_arrows: Option<usize>,
+}
+
+impl<R: SavedDice> Serialize for HuntTheWumpus<R> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Self::serialize(self, serializer)
+    }
+}
+
+impl<'de, R: SavedDice + SeedableRng> Deserialize<'de> for HuntTheWumpus<R> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut game = Self::deserialize(deserializer)?;
+        if let (Some(arrows), [player]) = (game.legacy_arrows.take(), &mut game.players[..]) {
+            player.arrows = arrows;
+        }
+        Ok(game)
+    }
 }
 
 impl HuntTheWumpus {
-    pub fn new() -> Self {
-        let ref mut rng = rand::thread_rng();
-        let mut points = (0..20).choose_multiple(rng, 6).into_iter();
-        Self {
-            player: Player {
-                room_index: points.next().unwrap(),
-            },
-            rooms: ROOMS.map(|connections| Room { connections }),
+    /// A new game for `players`, with dice seeded at random.
+    pub fn new(settings: Settings, players: &[u64]) -> Result<Self, CaveError> {
+        Self::seeded(settings, players, rand::random())
+    }
+}
+
+impl<R: Rng + SeedableRng> HuntTheWumpus<R> {
+    /// A new game for `players`, with dice seeded with `seed`. The same seed digs the same cave
+    /// and hides the same hazards in it.
+    pub fn seeded(settings: Settings, players: &[u64], seed: u64) -> Result<Self, CaveError> {
+        let mut game = Self::with_rng(settings, players, R::seed_from_u64(seed))?;
+        game.seed = Some(seed);
+        Ok(game)
+    }
+
+    /// Plays `log` again from the start of a game seeded with `seed`.
+    pub fn replay(settings: Settings, seed: u64, log: &[Turn]) -> Result<Self, ReplayError> {
+        let mut game = Self::seeded(settings, &[], seed).map_err(ReplayError::Cave)?;
+        for (index, turn) in log.iter().enumerate() {
+            if !game.play(turn) {
+                return Err(ReplayError::Illegal(index));
+            }
+        }
+        Ok(game)
+    }
+}
+
+impl<R: Rng> HuntTheWumpus<R> {
+    /// A new game for `players`, who take turns in that order, rolling `rng`. Players past
+    /// `MAX_PLAYERS` are left out.
+    pub fn with_rng(settings: Settings, players: &[u64], mut rng: R) -> Result<Self, CaveError> {
+        let mut rooms = Cave::new(settings.layout, settings.rooms, &mut rng)?;
+        let mut points = (0..rooms.len()).choose_multiple(&mut rng, 5).into_iter();
+        if settings.dark {
+            // buttons number the tunnels, which in room order would say where they lead
+            rooms.shuffle_tunnels(&mut rng);
+        }
+        let mut game = Self {
+            players: vec![],
+            rooms,
             wumpus: Wumpus {
                 room_index: points.next().unwrap(),
             },
             pits: [points.next().unwrap(), points.next().unwrap()],
             bats: [points.next().unwrap(), points.next().unwrap()],
-            arrows: 5,
             state: State::Running,
+            rules: settings.rules,
+            events: vec![],
+            layout: settings.layout,
+            dark: settings.dark,
+            mode: settings.mode,
+            turn: 0,
+            winner: None,
+            rng,
+            seed: None,
+            log: vec![],
+            legacy_arrows: None,
+        };
+        for id in players {
+            // the same player twice only joins once
+            let _ = game.join(*id);
+        }
+        Ok(game)
+    }
+
+    /// Plays a turn from a log. Returns whether it could be played.
+    pub fn play(&mut self, turn: &Turn) -> bool {
+        match turn {
+            Turn::Join(id) => self.join(*id).is_ok(),
+            Turn::Leave(id) => self.leave(*id).is_some(),
+            Turn::Move(room) => self.move_player_to(*room),
+            Turn::Shoot {
+                at_room,
+                room_travel,
+            } => self.shoot(*at_room, *room_travel),
+            Turn::ShootPath(path) => self.has_turn() && self.shoot_path(path).is_ok(),
+        }
+    }
+
+    /// Every turn played so far, starting with the players joining.
+    pub fn log(&self) -> &[Turn] {
+        &self.log
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The settings the game was started with.
+    pub fn settings(&self) -> Settings {
+        Settings {
+            rules: self.rules,
+            layout: self.layout,
+            rooms: self.rooms.len(),
+            dark: self.dark,
+            mode: self.mode,
+        }
+    }
+
+    /// Adds a player in a room with nothing dangerous in it or next to it, if there is one. They
+    /// take their turns after everyone already playing.
+    pub fn join(&mut self, id: u64) -> Result<(), JoinError> {
+        if !self.is_running() {
+            Err(JoinError::Over)
+        } else if self.has_player(id) {
+            Err(JoinError::AlreadyPlaying)
+        } else if self.players.len() >= MAX_PLAYERS {
+            Err(JoinError::Full)
+        } else {
+            self.add_player(id);
+            self.log.push(Turn::Join(id));
+            Ok(())
+        }
+    }
+
+    /// Takes a player out of the game. Returns how it went for them, if they were playing: the
+    /// fate they'd already met, or `Abandoned`.
+    pub fn leave(&mut self, id: u64) -> Option<Outcome> {
+        let index = self.players.iter().position(|player| player.id == id)?;
+        let player = self.players.remove(index);
+        let outcome = Outcome {
+            player: id,
+            result: player.fate.map_or("Abandoned", State::name),
+            moves: player.moves,
+            arrows_used: ARROWS.saturating_sub(player.arrows),
+        };
+        self.log.push(Turn::Leave(id));
+        if index < self.turn {
+            self.turn -= 1;
+        } else if index == self.turn && !self.players.is_empty() {
+            // the turn passes to whoever was after them
+            self.turn = (self.turn + self.players.len() - 1) % self.players.len();
+            self.end_turn();
+        }
+        if self.is_running() && !self.players.is_empty() {
+            if let Some(fate) = self.players.iter().find_map(|player| player.fate) {
+                if self.players.iter().all(|player| player.fate.is_some()) {
+                    self.state = fate;
+                }
+            }
+        }
+        Some(outcome)
+    }
+
+    fn add_player(&mut self, id: u64) {
+        let (rooms, rng) = (&self.rooms, &mut self.rng);
+        let dangers = [
+            self.wumpus.room_index,
+            self.pits[0],
+            self.pits[1],
+            self.bats[0],
+            self.bats[1],
+        ];
+        let safe = |room: &usize| !dangers.contains(room);
+        let room_index = (0..rooms.len())
+            .filter(|room| safe(room) && rooms.tunnels(*room).iter().all(safe))
+            .choose(rng)
+            .or_else(|| (0..rooms.len()).filter(safe).choose(rng))
+            .unwrap();
+        self.players.push(Player {
+            id,
+            room_index,
+            arrows: ARROWS,
+            fate: None,
+            seen: BTreeMap::new(),
+            moves: 0,
+        });
+        self.look(self.players.len() - 1);
+    }
+
+    /// Remembers what the player at `index` notices in their room.
+    fn look(&mut self, index: usize) {
+        let room = self.players[index].room_index;
+        let senses = self.senses(room);
+        self.players[index].seen.insert(room, senses);
+    }
+
+    fn senses(&self, room: usize) -> Senses {
+        let connections = self.rooms.tunnels(room);
+        Senses {
+            wumpus: connections.contains(&self.wumpus.room_index),
+            bats: self.bats.iter().any(|b| connections.contains(b)),
+            draft: self.pits.iter().any(|p| connections.contains(p)),
+        }
+    }
+
+    /// The player whose turn it is.
+    pub fn current_player(&self) -> u64 {
+        self.players[self.turn].id
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn has_player(&self, id: u64) -> bool {
+        self.players.iter().any(|player| player.id == id)
+    }
+
+    /// Whether `id` is still hunting. Players who met their end watch the rest of the game.
+    pub fn is_hunting(&self, id: u64) -> bool {
+        self.players
+            .iter()
+            .any(|player| player.id == id && player.fate.is_none())
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.dark
+    }
+
+    /// How the game went for each player, once it's over.
+    pub fn outcomes(&self) -> Vec<Outcome> {
+        if self.is_running() {
+            return vec![];
+        }
+        self.players
+            .iter()
+            .map(|player| Outcome {
+                player: player.id,
+                result: match (player.fate, self.mode) {
+                    (Some(fate), _) => fate.name(),
+                    (None, Mode::Versus) if self.winner != Some(player.id) => "Lost",
+                    (None, _) => self.state.name(),
+                },
+                moves: player.moves,
+                arrows_used: ARROWS.saturating_sub(player.arrows),
+            })
+            .collect()
+    }
+
+    /// What `id` knows about the cave, or whoever's turn it is if `id` isn't playing.
+    pub fn map(&self, id: u64) -> Map<'_> {
+        let player = self
+            .players
+            .iter()
+            .find(|player| player.id == id)
+            .unwrap_or(&self.players[self.turn]);
+        Map {
+            cave: &self.rooms,
+            layout: self.layout,
+            dark: self.dark,
+            room: player.room_index,
+            seen: &player.seen,
+            hunters: self
+                .players
+                .iter()
+                .filter(|other| other.id != player.id && other.fate.is_none())
+                .map(|other| other.room_index)
+                .collect(),
+            hazards: (!self.is_running()).then_some(Hazards {
+                wumpus: self.wumpus.room_index,
+                pits: self.pits,
+                bats: self.bats,
+            }),
         }
     }
 
+    /// The rooms the player whose turn it is can move or shoot into.
+    pub fn tunnels(&self) -> &[usize] {
+        self.rooms.tunnels(self.players[self.turn].room_index)
+    }
+
+    /// The rooms that the tunnels numbered `tunnels` lead through from the room of the player
+    /// whose turn it is. Dark caves tell tunnels apart by number, counting from 1 in the order
+    /// `tunnels()` lists them.
+    pub fn follow_tunnels(&self, tunnels: &[usize]) -> Result<Vec<usize>, PathError> {
+        let mut room = self.players[self.turn].room_index;
+        let mut path: Vec<usize> = vec![];
+        for (i, &tunnel) in tunnels.iter().enumerate() {
+            room = *tunnel
+                .checked_sub(1)
+                .and_then(|index| self.rooms.tunnels(room).get(index))
+                .ok_or(PathError::NoSuchTunnel(tunnel))?;
+            if i >= 2 && path[i - 2] == room {
+                return Err(PathError::TooCrookedTunnel(tunnel));
+            }
+            path.push(room);
+        }
+        Ok(path)
+    }
+
+    pub fn arrows(&self) -> usize {
+        self.players[self.turn].arrows
+    }
+
     pub fn status(&self) -> String {
+        use std::fmt::Write;
+        let together = self.players.len() > 1;
+        let mut out = String::new();
+        for event in &self.events {
+            match event {
+                Event::BumpedWumpus => writeln!(&mut out, "...Oops! Bumped a Wumpus!"),
+                Event::BatSnatch => {
+                    writeln!(&mut out, "ZAP--Super bat snatch! Elsewhereville for you!")
+                }
+                Event::Missed => writeln!(&mut out, "Missed!"),
+                Event::Died { player, how } => {
+                    writeln!(&mut out, "<@{}> {}", player, fate(*how))
+                }
+            }
+            .unwrap();
+        }
         match self.state {
             State::Running => {
-                let connections = self.rooms[self.player.room_index].connections;
-                let [a, b, c] = connections;
-                let mut out = String::new();
-                use std::fmt::Write;
-                writeln!(&mut out, "You're in room {}.", self.player.room_index).unwrap();
-                writeln!(&mut out, "Tunnels lead to {}, {}, {}", a, b, c).unwrap();
-                if connections.contains(&self.wumpus.room_index) {
+                let player = &self.players[self.turn];
+                let connections = self.tunnels();
+                if together {
+                    writeln!(&mut out, "**<@{}>'s turn.**", player.id).unwrap();
+                }
+                writeln!(&mut out, "You're in room {}.", player.room_index).unwrap();
+                if self.dark {
+                    writeln!(
+                        &mut out,
+                        "{} tunnels lead off into the dark.",
+                        connections.len()
+                    )
+                    .unwrap();
+                } else {
+                    let rooms = connections
+                        .iter()
+                        .map(|room| room.to_string())
+                        .collect::<Vec<_>>();
+                    writeln!(&mut out, "Tunnels lead to {}", rooms.join(", ")).unwrap();
+                }
+                let senses = self.senses(player.room_index);
+                if senses.wumpus {
                     writeln!(&mut out, "I smell a Wumpus.").unwrap();
                 }
-                if self.bats.iter().any(|b| connections.contains(b)) {
+                if senses.bats {
                     writeln!(&mut out, "Bats nearby!").unwrap();
                 }
-                if self.pits.iter().any(|p| connections.contains(p)) {
+                if senses.draft {
                     writeln!(&mut out, "I feel a draft.").unwrap();
                 }
-                out
+                if together {
+                    let hunters = self
+                        .players
+                        .iter()
+                        .map(|player| match player.fate {
+                            None => format!("<@{}> in room {}", player.id, player.room_index),
+                            Some(how) => format!("<@{}> {}", player.id, fate(how)),
+                        })
+                        .collect::<Vec<_>>();
+                    writeln!(&mut out, "Hunters: {}", hunters.join(", ")).unwrap();
+                }
             }
-            State::Won => format!("You killed the Wumpus!"),
-            State::GameOverShot => format!("You shoot yourself!"),
-            State::GameOverWumpus => format!("HE HE HE! The Wumpus got ya!"),
-            State::GameOverPit => format!("YIIIEEEE... fell in a pit."),
-            State::GameOverArrows => format!("Out of arrows!"),
+            State::Won => match (together, self.winner, self.mode) {
+                (true, Some(winner), Mode::Coop) => {
+                    writeln!(&mut out, "<@{}> killed the Wumpus! Everyone wins!", winner).unwrap()
+                }
+                (true, Some(winner), Mode::Versus) => {
+                    writeln!(&mut out, "<@{}> killed the Wumpus and wins!", winner).unwrap()
+                }
+                _ => writeln!(&mut out, "You killed the Wumpus!").unwrap(),
+            },
+            _ if together => writeln!(&mut out, "Nobody's left to hunt the Wumpus.").unwrap(),
+            State::GameOverShot => writeln!(&mut out, "You shoot yourself!").unwrap(),
+            State::GameOverWumpus => writeln!(&mut out, "HE HE HE! The Wumpus got ya!").unwrap(),
+            State::GameOverPit => writeln!(&mut out, "YIIIEEEE... fell in a pit.").unwrap(),
+            State::GameOverArrows => writeln!(&mut out, "Out of arrows!").unwrap(),
         }
+        out
     }
 
     pub fn move_player_to(&mut self, new_room: usize) -> bool {
-        let current = &self.rooms[self.player.room_index];
-        if current.connections.contains(&new_room) {
-            self.player.room_index = new_room;
-            self.update_state();
+        if self.has_turn() && self.tunnels().contains(&new_room) {
+            self.events.clear();
+            self.log.push(Turn::Move(new_room));
+            self.players[self.turn].room_index = new_room;
+            self.players[self.turn].moves += 1;
+            match self.rules {
+                Rules::Classic => self.enter_room(),
+                Rules::Current => self.update_state(),
+            }
+            self.look(self.turn);
+            self.end_turn();
             true
         } else {
             false
@@ -104,62 +741,570 @@ impl HuntTheWumpus {
     }
 
     pub fn shoot(&mut self, at_room: usize, room_travel: usize) -> bool {
-        let current = &self.rooms[self.player.room_index];
-        if current.connections.contains(&at_room) {
-            self.arrows -= 1;
-            let ref mut rng = rand::thread_rng();
+        if self.has_turn() && self.tunnels().contains(&at_room) {
+            self.events.clear();
+            self.log.push(Turn::Shoot {
+                at_room,
+                room_travel,
+            });
+            self.players[self.turn].arrows -= 1;
             let mut current = at_room;
             for _ in 0..room_travel {
-                if self.wumpus.room_index == current {
-                    self.state = State::Won;
-                    return true;
-                }
-                if self.player.room_index == current {
-                    self.state = State::GameOverShot;
-                    return true;
+                if self.arrow_hits(current) {
+                    break;
                 }
-                current = self.rooms[current]
-                    .connections
-                    .choose(rng)
-                    .copied()
-                    .unwrap();
-            }
-            if self.arrows == 0 {
-                self.state = State::GameOverArrows;
+                current = *self.rooms.tunnels(current).choose(&mut self.rng).unwrap();
             }
+            self.check_arrows();
+            self.end_turn();
             true
         } else {
             false
         }
     }
 
+    /// Shoots an arrow through `path` under the classic rules. When the next room in the path
+    /// isn't connected to the arrow's room, the arrow flies down a random tunnel instead. If it
+    /// misses, the Wumpus wakes.
+    pub fn shoot_path(&mut self, path: &[usize]) -> Result<(), PathError> {
+        if path.is_empty() || path.len() > MAX_ARROW_PATH {
+            return Err(PathError::Length);
+        }
+        if let Some(room) = path.iter().find(|room| **room >= self.rooms.len()) {
+            return Err(PathError::NoSuchRoom(*room));
+        }
+        if let Some(room) = (2..path.len()).find_map(|i| (path[i] == path[i - 2]).then(|| path[i]))
+        {
+            return Err(PathError::TooCrooked(room));
+        }
+        if !self.has_turn() {
+            return Ok(());
+        }
+
+        self.events.clear();
+        self.log.push(Turn::ShootPath(path.to_vec()));
+        self.players[self.turn].arrows -= 1;
+        let mut current = self.players[self.turn].room_index;
+        let mut hit = false;
+        for next in path {
+            let connections = self.rooms.tunnels(current);
+            current = if connections.contains(next) {
+                *next
+            } else {
+                *connections.choose(&mut self.rng).unwrap()
+            };
+            if self.arrow_hits(current) {
+                hit = true;
+                break;
+            }
+        }
+
+        if !hit {
+            self.events.push(Event::Missed);
+            self.wake_wumpus();
+        }
+        self.check_arrows();
+        self.end_turn();
+        Ok(())
+    }
+
     pub fn is_running(&self) -> bool {
         std::matches!(self.state, State::Running)
     }
 
+    /// Whether there's anyone to take a turn. A game being replayed has nobody in it until its
+    /// players join.
+    fn has_turn(&self) -> bool {
+        self.is_running() && !self.players.is_empty()
+    }
+
+    /// Whether an arrow flying into `room` hits the Wumpus or whoever is standing there, who
+    /// may well be the player who shot it.
+    fn arrow_hits(&mut self, room: usize) -> bool {
+        if self.wumpus.room_index == room {
+            self.state = State::Won;
+            self.winner = Some(self.players[self.turn].id);
+            return true;
+        }
+        let hunters = self.players.len();
+        let hit = (0..hunters)
+            .map(|i| (self.turn + i) % hunters)
+            .find(|i| self.players[*i].fate.is_none() && self.players[*i].room_index == room);
+        match hit {
+            Some(hit) => {
+                self.end(hit, State::GameOverShot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the game for the shooter if that was their last arrow, unless it won the game.
+    fn check_arrows(&mut self) {
+        let player = &self.players[self.turn];
+        if self.is_running() && player.fate.is_none() && player.arrows == 0 {
+            self.end(self.turn, State::GameOverArrows);
+        }
+    }
+
+    /// Ends the game for one player. The game is over once it's over for everyone.
+    fn end(&mut self, index: usize, how: State) {
+        self.players[index].fate = Some(how);
+        if self.players.len() > 1 {
+            self.events.push(Event::Died {
+                player: self.players[index].id,
+                how,
+            });
+        }
+        if self.players.iter().all(|player| player.fate.is_some()) {
+            self.state = how;
+        }
+    }
+
+    /// Passes the turn to the next player still hunting.
+    fn end_turn(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let hunters = self.players.len();
+        if let Some(next) = (1..=hunters)
+            .map(|i| (self.turn + i) % hunters)
+            .find(|i| self.players[*i].fate.is_none())
+        {
+            self.turn = next;
+        }
+    }
+
     fn update_state(&mut self) {
-        if self.wumpus.room_index == self.player.room_index {
-            self.state = State::GameOverWumpus
-        } else if self.bats.contains(&self.player.room_index) {
-            self.player.room_index = rand::thread_rng().gen_range(0..self.rooms.len());
+        let room = self.players[self.turn].room_index;
+        if self.wumpus.room_index == room {
+            self.end(self.turn, State::GameOverWumpus)
+        } else if self.bats.contains(&room) {
+            self.players[self.turn].room_index = self.rng.gen_range(0..self.rooms.len());
             self.update_state();
-        } else if self.pits.contains(&self.player.room_index) {
-            self.state = State::GameOverPit
+        } else if self.pits.contains(&room) {
+            self.end(self.turn, State::GameOverPit)
+        }
+    }
+
+    /// The hazards of the player's room under the classic rules. Bumping the Wumpus wakes it,
+    /// and bats carry the player to any room at all, where its hazards are met in turn.
+    fn enter_room(&mut self) {
+        if self.wumpus.room_index == self.players[self.turn].room_index {
+            self.events.push(Event::BumpedWumpus);
+            self.wake_wumpus();
+            if self.players[self.turn].fate.is_some() {
+                return;
+            }
+        }
+        let room = self.players[self.turn].room_index;
+        if self.pits.contains(&room) {
+            self.end(self.turn, State::GameOverPit)
+        } else if self.bats.contains(&room) {
+            self.events.push(Event::BatSnatch);
+            self.players[self.turn].room_index = self.rng.gen_range(0..self.rooms.len());
+            self.enter_room();
+        }
+    }
+
+    /// A woken Wumpus moves down one of its tunnels three times out of four, and eats anyone
+    /// in the room it ends up in.
+    fn wake_wumpus(&mut self) {
+        let connections = self.rooms.tunnels(self.wumpus.room_index);
+        if let Some(room) = connections
+            .iter()
+            .chain(std::iter::once(&self.wumpus.room_index))
+            .choose(&mut self.rng)
+        {
+            self.wumpus.room_index = *room;
+        }
+        for i in 0..self.players.len() {
+            let player = &self.players[i];
+            if player.fate.is_none() && player.room_index == self.wumpus.room_index {
+                self.end(i, State::GameOverWumpus);
+            }
+        }
+    }
+}
+
+/// How a player's game ended, as told to the others.
+fn fate(how: State) -> &'static str {
+    match how {
+        State::Running => "is still hunting.",
+        State::Won => "killed the Wumpus!",
+        State::GameOverShot => "was shot!",
+        State::GameOverWumpus => "was eaten by the Wumpus!",
+        State::GameOverPit => "fell in a pit.",
+        State::GameOverArrows => "ran out of arrows.",
+    }
+}
+
+/// Games saved before there could be more than one player had a single `player`.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Player>, D::Error> {
+    use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+
+    struct OneOrMany;
+
+    impl<'de> serde::de::Visitor<'de> for OneOrMany {
+        type Value = Vec<Player>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a player or a list of players")
+        }
+
+        fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            Ok(vec![Player::deserialize(MapAccessDeserializer::new(map))?])
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(SeqAccessDeserializer::new(seq))
         }
     }
+
+    deserializer.deserialize_any(OneOrMany)
+}
+
+fn full_quiver() -> usize {
+    ARROWS
+}
+
+fn unseeded<R: SeedableRng>() -> R {
+    R::from_entropy()
+}
+
+fn save_dice<R: SavedDice, S: serde::Serializer>(
+    rng: &R,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    rng.save().serialize(serializer)
+}
+
+fn load_dice<'de, R: SavedDice, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<R, D::Error> {
+    let state = DiceState::deserialize(deserializer)?;
+    R::load(state).map_err(serde::de::Error::custom)
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Player {
+    #[serde(default)]
+    pub id: u64,
     pub room_index: usize,
+    #[serde(default = "full_quiver")]
+    pub arrows: usize,
+    /// How the game ended for this player, while others play on.
+    #[serde(default)]
+    pub fate: Option<State>,
+    #[serde(default)]
+    pub seen: BTreeMap<usize, Senses>,
+    #[serde(default)]
+    pub moves: usize,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 struct Wumpus {
     pub room_index: usize,
 }
 
-#[derive(Debug, Copy, Clone)]
-struct Room {
-    pub connections: [usize; 3],
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dodecahedron with the Wumpus in room 7, pits in rooms 1 and 10 and bats in rooms 4 and
+    /// 12. Everyone starts in room 0, whose tunnels lead to rooms 1, 4 and 7.
+    fn game(rules: Rules, players: &[u64]) -> HuntTheWumpus {
+        let settings = Settings {
+            rules,
+            ..Settings::default()
+        };
+        let mut game = HuntTheWumpus::<Dice>::seeded(settings, players, 0).unwrap();
+        game.wumpus.room_index = 7;
+        game.pits = [1, 10];
+        game.bats = [4, 12];
+        for player in &mut game.players {
+            player.room_index = 0;
+        }
+        game
+    }
+
+    #[test]
+    fn falls_in_pits() {
+        for rules in [Rules::Classic, Rules::Current] {
+            let mut game = game(rules, &[1]);
+            assert!(game.move_player_to(1));
+            assert_eq!(game.state, State::GameOverPit);
+            assert!(game.status().contains("fell in a pit"));
+        }
+    }
+
+    #[test]
+    fn bats_carry_players_off() {
+        for rules in [Rules::Classic, Rules::Current] {
+            let mut game = game(rules, &[1]);
+            assert!(game.move_player_to(4));
+            assert!(!game.bats.contains(&game.players[0].room_index));
+            if rules == Rules::Classic {
+                assert_eq!(game.events[0], Event::BatSnatch);
+            }
+        }
+    }
+
+    #[test]
+    fn the_wumpus_eats_players() {
+        let mut game = game(Rules::Current, &[1]);
+        assert!(game.move_player_to(7));
+        assert_eq!(game.state, State::GameOverWumpus);
+    }
+
+    #[test]
+    fn bumping_the_wumpus_wakes_it() {
+        for seed in 0..20 {
+            let mut game = game(Rules::Classic, &[1]);
+            game.rng = Dice::seed_from_u64(seed);
+            assert!(game.move_player_to(7));
+            assert_eq!(game.events[0], Event::BumpedWumpus);
+            // it only eats the player if it stays put
+            assert_eq!(game.is_running(), game.wumpus.room_index != 7);
+        }
+    }
+
+    #[test]
+    fn shooting_the_wumpus_wins() {
+        let mut game = game(Rules::Current, &[1]);
+        assert!(game.shoot(7, 1));
+        assert_eq!(game.state, State::Won);
+        assert_eq!(game.winner, Some(1));
+    }
+
+    #[test]
+    fn arrows_can_come_back() {
+        let mut game = game(Rules::Classic, &[1]);
+        assert_eq!(game.shoot_path(&[1, 0]), Ok(()));
+        assert_eq!(game.state, State::GameOverShot);
+        assert!(game.status().contains("You shoot yourself!"));
+    }
+
+    #[test]
+    fn players_can_shoot_each_other() {
+        let mut game = game(Rules::Classic, &[1, 2]);
+        game.players[1].room_index = 2;
+        assert_eq!(game.shoot_path(&[1, 2]), Ok(()));
+        assert_eq!(game.players[1].fate, Some(State::GameOverShot));
+        assert_eq!(
+            game.events,
+            [Event::Died {
+                player: 2,
+                how: State::GameOverShot
+            }]
+        );
+        assert!(game.is_running());
+        assert_eq!(game.current_player(), 1);
+    }
+
+    #[test]
+    fn shooting_another_player_with_the_last_arrow() {
+        for rules in [Rules::Classic, Rules::Current] {
+            let mut game = game(rules, &[1, 2, 3]);
+            game.players[0].arrows = 1;
+            game.players[1].room_index = 4;
+            match rules {
+                Rules::Classic => assert_eq!(game.shoot_path(&[4]), Ok(())),
+                Rules::Current => assert!(game.shoot(4, 1)),
+            }
+            assert_eq!(game.players[0].arrows, 0);
+            assert_eq!(game.players[0].fate, Some(State::GameOverArrows));
+            assert_eq!(game.players[1].fate, Some(State::GameOverShot));
+            assert!(game.is_running());
+            assert_eq!(game.current_player(), 3);
+        }
+    }
+
+    #[test]
+    fn running_out_of_arrows() {
+        let mut game = game(Rules::Current, &[1]);
+        game.players[0].arrows = 2;
+        assert!(game.shoot(4, 1));
+        assert_eq!(game.arrows(), 1);
+        assert!(game.is_running());
+        assert!(game.shoot(4, 1));
+        assert_eq!(game.state, State::GameOverArrows);
+    }
+
+    #[test]
+    fn crooked_paths() {
+        let mut game = game(Rules::Classic, &[1]);
+        assert_eq!(game.shoot_path(&[]), Err(PathError::Length));
+        assert_eq!(game.shoot_path(&[1, 2, 3, 4, 5, 6]), Err(PathError::Length));
+        assert_eq!(game.shoot_path(&[1, 20]), Err(PathError::NoSuchRoom(20)));
+        assert_eq!(game.shoot_path(&[1, 0, 1]), Err(PathError::TooCrooked(1)));
+        assert!(game.log().iter().all(|turn| matches!(turn, Turn::Join(_))));
+    }
+
+    #[test]
+    fn players_take_turns() {
+        let mut game = game(Rules::Current, &[1, 2, 3]);
+        assert_eq!(game.join(2), Err(JoinError::AlreadyPlaying));
+        for player in [1, 2, 3, 1] {
+            assert_eq!(game.current_player(), player);
+            assert!(game.shoot(4, 1));
+        }
+        let outcome = Outcome {
+            player: 2,
+            result: "Abandoned",
+            moves: 0,
+            arrows_used: 1,
+        };
+        assert_eq!(game.leave(2), Some(outcome));
+        assert_eq!(game.current_player(), 3);
+        assert_eq!(game.leave(2), None);
+    }
+
+    #[test]
+    fn following_tunnels() {
+        let game = game(Rules::Classic, &[1]);
+        let tunnels = game.tunnels().to_vec();
+        assert_eq!(game.follow_tunnels(&[2]), Ok(vec![tunnels[1]]));
+        assert_eq!(game.follow_tunnels(&[0]), Err(PathError::NoSuchTunnel(0)));
+        assert_eq!(game.follow_tunnels(&[4]), Err(PathError::NoSuchTunnel(4)));
+        let number = |from: usize, to: usize| {
+            game.rooms
+                .tunnels(from)
+                .iter()
+                .position(|&room| room == to)
+                .unwrap()
+                + 1
+        };
+        let there = number(0, tunnels[0]);
+        let back = number(tunnels[0], 0);
+        assert_eq!(game.follow_tunnels(&[there, back]), Ok(vec![tunnels[0], 0]));
+        assert_eq!(
+            game.follow_tunnels(&[there, back, there]),
+            Err(PathError::TooCrookedTunnel(there))
+        );
+    }
+
+    #[test]
+    fn outcomes_once_the_game_is_over() {
+        let mut game = game(Rules::Current, &[1, 2, 3]);
+        game.mode = Mode::Versus;
+        assert!(game.shoot(4, 1));
+        assert!(game.move_player_to(1));
+        assert!(game.outcomes().is_empty());
+        assert!(game.shoot(7, 1));
+        let result = |outcome: &Outcome| {
+            (
+                outcome.player,
+                outcome.result,
+                outcome.moves,
+                outcome.arrows_used,
+            )
+        };
+        assert_eq!(
+            game.outcomes().iter().map(result).collect::<Vec<_>>(),
+            [
+                (1, "Lost", 0, 1),
+                (2, "GameOverPit", 1, 0),
+                (3, "Won", 0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_hide_hazards_until_the_game_is_over() {
+        let mut game = game(Rules::Current, &[1, 2]);
+        game.players[1].room_index = 2;
+        assert!(game.shoot(4, 1));
+        let map = game.map(1);
+        assert_eq!(map.room, 0);
+        assert_eq!(map.hunters, [2]);
+        assert_eq!(map.hazards, None);
+
+        assert!(game.move_player_to(1));
+        assert!(game.shoot(7, 1));
+        let map = game.map(2);
+        assert_eq!(map.room, 1);
+        assert!(map.seen.contains_key(&1));
+        assert_eq!(map.hunters, [0]);
+        assert_eq!(
+            map.hazards,
+            Some(Hazards {
+                wumpus: 7,
+                pits: [1, 10],
+                bats: [4, 12]
+            })
+        );
+    }
+
+    #[test]
+    fn seeded_games_replay() {
+        let settings = Settings {
+            rules: Rules::Classic,
+            layout: Layout::Random,
+            ..Settings::default()
+        };
+        let mut game = HuntTheWumpus::<Dice>::seeded(settings, &[1, 2], 42).unwrap();
+        let mut choices = Dice::seed_from_u64(7);
+        while game.is_running() && game.log().len() < 100 {
+            let room = *game.tunnels().choose(&mut choices).unwrap();
+            if choices.gen_bool(0.2) {
+                game.shoot_path(&[room]).unwrap();
+            } else {
+                assert!(game.move_player_to(room));
+            }
+        }
+
+        let replayed = HuntTheWumpus::<Dice>::replay(game.settings(), 42, game.log()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&replayed).unwrap(),
+            serde_json::to_value(&game).unwrap()
+        );
+        assert_eq!(
+            HuntTheWumpus::<Dice>::replay(game.settings(), 42, &[Turn::Move(100)]).unwrap_err(),
+            ReplayError::Illegal(0)
+        );
+    }
+
+    #[test]
+    fn saved_games_roll_on() {
+        let mut game = game(Rules::Classic, &[1]);
+        let mut saved: HuntTheWumpus =
+            serde_json::from_value(serde_json::to_value(&game).unwrap()).unwrap();
+        for _ in 0..10 {
+            assert_eq!(game.rng.gen::<u64>(), saved.rng.gen::<u64>());
+        }
+        assert_eq!(saved.seed(), Some(0));
+    }
+
+    #[test]
+    fn games_round_trip_through_json_values() {
+        let mut game = game(Rules::Classic, &[1, 2]);
+        assert!(game.shoot_path(&[4]).is_ok());
+        assert!(game.shoot_path(&[1]).is_ok());
+        // saving goes through a `Value`, which can't hold a `u128`
+        let value = serde_json::to_value(&game).unwrap();
+        let mut saved: HuntTheWumpus = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&saved).unwrap(), value);
+        assert_eq!(saved.log(), game.log());
+        assert_eq!(saved.current_player(), game.current_player());
+        assert_eq!(saved.rng.gen::<u64>(), game.rng.gen::<u64>());
+    }
+
+    #[test]
+    fn games_saved_before_multiplayer_load() {
+        let saved = serde_json::json!({
+            "player": { "room_index": 0 },
+            "rooms": Cave::dodecahedron(),
+            "wumpus": { "room_index": 7 },
+            "pits": [1, 10],
+            "bats": [4, 12],
+            "state": "Running",
+            "arrows": 3,
+        });
+        let game: HuntTheWumpus = serde_json::from_value(saved).unwrap();
+        assert_eq!(game.player_count(), 1);
+        assert_eq!(game.arrows(), 3);
+        assert_eq!(game.tunnels(), [1, 4, 7]);
+        assert_eq!(game.seed(), None);
+    }
 }
@@ -0,0 +1,318 @@
+//! The caves the Wumpus lives in. Whatever their shape, tunnels go both ways and every room can
+//! be reached from every other.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Enough rooms for the player, the Wumpus, two pits and two bats, with some to spare.
+pub const MIN_ROOMS: usize = 8;
+pub const MAX_ROOMS: usize = 100;
+
+/// The rooms of the original game, on the corners of a dodecahedron.
+const DODECAHEDRON: [[usize; 3]; 20] = [
+    [1, 4, 7],
+    [0, 2, 9],
+    [1, 3, 11],
+    [2, 4, 13],
+    [0, 3, 5],
+    [4, 6, 14],
+    [5, 7, 16],
+    [0, 6, 8],
+    [7, 9, 17],
+    [1, 8, 10],
+    [9, 11, 18],
+    [2, 10, 12],
+    [11, 13, 19],
+    [3, 12, 14],
+    [5, 13, 15],
+    [14, 16, 19],
+    [6, 15, 17],
+    [8, 16, 18],
+    [10, 17, 19],
+    [12, 15, 18],
+];
+
+/// Rows of the torus. It has to be even for the honeycomb to wrap around.
+pub const TORUS_ROWS: usize = 4;
+
+/// How many times to shuffle tunnels before giving up on a random cave.
+const RANDOM_ATTEMPTS: usize = 1000;
+
+/// The shapes a cave can take. All of them have three tunnels from every room.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layout {
+    #[default]
+    Dodecahedron,
+    /// A honeycomb wrapped around a torus, like the hex network of Wumpus II.
+    Torus,
+    /// A ladder whose ends are joined with a twist.
+    Mobius,
+    /// Tunnels dug at random between any rooms.
+    Random,
+}
+
+impl Layout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Dodecahedron => "Dodecahedron",
+            Layout::Torus => "Torus",
+            Layout::Mobius => "Möbius strip",
+            Layout::Random => "Random cave",
+        }
+    }
+}
+
+impl std::str::FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dodecahedron" => Ok(Layout::Dodecahedron),
+            "torus" => Ok(Layout::Torus),
+            "mobius" => Ok(Layout::Mobius),
+            "random" => Ok(Layout::Random),
+            _ => Err(format!("Unknown cave: {}", s)),
+        }
+    }
+}
+
+/// Why a cave can't be dug.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaveError {
+    Size {
+        layout: Layout,
+        rooms: usize,
+    },
+    TooSmall(usize),
+    NoSuchRoom {
+        from: usize,
+        to: usize,
+    },
+    Loop(usize),
+    Duplicate {
+        from: usize,
+        to: usize,
+    },
+    OneWay {
+        from: usize,
+        to: usize,
+    },
+    Unreachable(usize),
+    /// No random cave could be dug in a reasonable number of tries.
+    GaveUp,
+}
+
+impl std::fmt::Display for CaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaveError::Size { layout, rooms } => write!(
+                f,
+                "A {} can't have {} rooms. {}",
+                layout.name(),
+                rooms,
+                match layout {
+                    Layout::Dodecahedron => "It always has 20.".to_owned(),
+                    Layout::Torus => format!(
+                        "It needs a multiple of {} between {} and {}.",
+                        TORUS_ROWS,
+                        TORUS_ROWS * 3,
+                        MAX_ROOMS
+                    ),
+                    Layout::Mobius | Layout::Random => format!(
+                        "It needs an even number between {} and {}.",
+                        MIN_ROOMS, MAX_ROOMS
+                    ),
+                }
+            ),
+            CaveError::TooSmall(rooms) => write!(
+                f,
+                "A cave needs at least {} rooms, not {}.",
+                MIN_ROOMS, rooms
+            ),
+            CaveError::NoSuchRoom { from, to } => {
+                write!(
+                    f,
+                    "Room {} has a tunnel to room {}, which doesn't exist.",
+                    from, to
+                )
+            }
+            CaveError::Loop(room) => write!(f, "Room {} has a tunnel to itself.", room),
+            CaveError::Duplicate { from, to } => {
+                write!(f, "Room {} has two tunnels to room {}.", from, to)
+            }
+            CaveError::OneWay { from, to } => write!(
+                f,
+                "Room {} has a tunnel to room {}, but not the other way.",
+                from, to
+            ),
+            CaveError::Unreachable(room) => write!(f, "Room {} can't be reached.", room),
+            CaveError::GaveUp => write!(f, "Couldn't dig a random cave. Try again."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct Room {
+    pub connections: Vec<usize>,
+}
+
+/// The rooms of a cave and the tunnels between them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cave {
+    rooms: Vec<Room>,
+}
+
+impl Cave {
+    /// A cave of `layout` with `rooms` rooms. Dodecahedrons always have 20.
+    pub fn new<R: Rng>(layout: Layout, rooms: usize, rng: &mut R) -> Result<Self, CaveError> {
+        let size = CaveError::Size { layout, rooms };
+        match layout {
+            Layout::Dodecahedron if rooms == DODECAHEDRON.len() => Ok(Self::dodecahedron()),
+            Layout::Torus
+                if rooms.is_multiple_of(TORUS_ROWS)
+                    && (TORUS_ROWS * 3..=MAX_ROOMS).contains(&rooms) =>
+            {
+                Self::torus(TORUS_ROWS, rooms / TORUS_ROWS)
+            }
+            Layout::Mobius
+                if rooms.is_multiple_of(2) && (MIN_ROOMS..=MAX_ROOMS).contains(&rooms) =>
+            {
+                Self::mobius(rooms)
+            }
+            Layout::Random
+                if rooms.is_multiple_of(2) && (MIN_ROOMS..=MAX_ROOMS).contains(&rooms) =>
+            {
+                Self::random(rooms, rng)
+            }
+            _ => Err(size),
+        }
+    }
+
+    pub fn dodecahedron() -> Self {
+        Self {
+            rooms: DODECAHEDRON
+                .iter()
+                .map(|connections| Room {
+                    connections: connections.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// A honeycomb of `rows` by `columns` rooms that wraps around in both directions. Every
+    /// room has tunnels to its left and right, and one more up or down, alternating like the
+    /// bricks of a wall.
+    pub fn torus(rows: usize, columns: usize) -> Result<Self, CaveError> {
+        let room = |row: usize, column: usize| (row % rows) * columns + column % columns;
+        let tunnels = (0..rows * columns)
+            .map(|index| {
+                let (row, column) = (index / columns, index % columns);
+                let vertical = if (row + column) % 2 == 0 {
+                    room(row + 1, column)
+                } else {
+                    room(row + rows - 1, column)
+                };
+                vec![
+                    room(row, column + columns - 1),
+                    room(row, column + 1),
+                    vertical,
+                ]
+            })
+            .collect();
+        Self::from_tunnels(tunnels)
+    }
+
+    /// A ring of `rooms` rooms, each with a tunnel straight across to the far side of the
+    /// ring. Followed around, the two sides of the ladder swap places.
+    pub fn mobius(rooms: usize) -> Result<Self, CaveError> {
+        let tunnels = (0..rooms)
+            .map(|room| {
+                vec![
+                    (room + rooms - 1) % rooms,
+                    (room + 1) % rooms,
+                    (room + rooms / 2) % rooms,
+                ]
+            })
+            .collect();
+        Self::from_tunnels(tunnels)
+    }
+
+    /// A random cave with three tunnels from each of `rooms` rooms. Tunnel ends are paired up
+    /// at random until they make a cave with no loops, doubled tunnels or cut off rooms.
+    pub fn random<R: Rng>(rooms: usize, rng: &mut R) -> Result<Self, CaveError> {
+        let mut ends = (0..rooms)
+            .flat_map(|room| std::iter::repeat_n(room, 3))
+            .collect::<Vec<_>>();
+        for _ in 0..RANDOM_ATTEMPTS {
+            ends.shuffle(rng);
+            let mut tunnels = vec![vec![]; rooms];
+            for pair in ends.chunks(2) {
+                tunnels[pair[0]].push(pair[1]);
+                tunnels[pair[1]].push(pair[0]);
+            }
+            if let Ok(cave) = Self::from_tunnels(tunnels) {
+                return Ok(cave);
+            }
+        }
+        Err(CaveError::GaveUp)
+    }
+
+    /// A cave with `tunnels[room]` leading out of each room, as long as every tunnel goes
+    /// both ways and every room can be reached.
+    pub fn from_tunnels(tunnels: Vec<Vec<usize>>) -> Result<Self, CaveError> {
+        if tunnels.len() < MIN_ROOMS {
+            return Err(CaveError::TooSmall(tunnels.len()));
+        }
+        for (from, connections) in tunnels.iter().enumerate() {
+            for (i, &to) in connections.iter().enumerate() {
+                if to >= tunnels.len() {
+                    return Err(CaveError::NoSuchRoom { from, to });
+                }
+                if to == from {
+                    return Err(CaveError::Loop(from));
+                }
+                if connections[..i].contains(&to) {
+                    return Err(CaveError::Duplicate { from, to });
+                }
+                if !tunnels[to].contains(&from) {
+                    return Err(CaveError::OneWay { from, to });
+                }
+            }
+        }
+
+        let mut reached = vec![false; tunnels.len()];
+        let mut queue = vec![0];
+        while let Some(room) = queue.pop() {
+            if !std::mem::replace(&mut reached[room], true) {
+                queue.extend(&tunnels[room]);
+            }
+        }
+        if let Some(room) = reached.iter().position(|reached| !reached) {
+            return Err(CaveError::Unreachable(room));
+        }
+
+        Ok(Self {
+            rooms: tunnels
+                .into_iter()
+                .map(|connections| Room { connections })
+                .collect(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn tunnels(&self, room: usize) -> &[usize] {
+        &self.rooms[room].connections
+    }
+
+    /// Shuffles the order of each room's tunnels, so that it doesn't give away where they lead.
+    pub fn shuffle_tunnels<R: Rng>(&mut self, rng: &mut R) {
+        for room in &mut self.rooms {
+            room.connections.shuffle(rng);
+        }
+    }
+}